@@ -4,13 +4,75 @@ use crate::keys::{KeyCombo, Key, ModKey};
 use std::hash::Hash;
 use crate::config::Config;
 use std::collections::HashMap;
+use crate::layouts::manual::Direction;
+use crate::layouts::modifiers::Modifier;
+use crate::session::SessionRequest;
+use crate::error::Error;
+use std::convert::TryFrom;
 
 pub enum Command {
     Spawn(String),
     NextWindow,
     PreviousWindow,
+    SwapNext,
+    SwapPrevious,
+    SwapMaster,
+    RotateStack,
+    FocusLast,
+    FocusHistoryBack,
+    FocusHistoryForward,
     GoToWorkspace(usize),
+    GoToPreviousWorkspace,
+    NextWorkspace,
+    PreviousWorkspace,
+    NextNonEmptyWorkspace,
+    PreviousNonEmptyWorkspace,
+    FirstEmptyWorkspace,
+    AddWorkspace(String),
+    RenameWorkspace(String),
+    DeleteWorkspace,
+    MoveWorkspaceNext,
+    MoveWorkspacePrevious,
+    FocusNextScreen,
+    FocusPreviousScreen,
+    FocusScreen(usize),
+    MoveWindowToScreen(usize),
+    SwapWorkspacesWithScreen(usize),
+    PreselectSplit(Direction),
+    ToggleSplitDirection,
+    ResizeSplit(f32),
+    MoveWindowToNextSplit,
+    MoveWindowToPreviousSplit,
+    ToggleModifier(Modifier),
+    ChangeRatio(f32),
+    ChangeMasterCount(isize),
+    SaveSession(String),
+    RestoreSession(String),
     Quit,
+    Restart,
+}
+
+/// Parses the commands sent with `rwm --command`, such as `add_workspace web`.
+/// Session names are file names in the session directory.
+impl TryFrom<&str> for Command {
+    type Error = Error;
+
+    fn try_from(display: &str) -> Result<Self, Self::Error> {
+        let mut parts = display.trim().splitn(2, ' ');
+        let argument = |argument: Option<&str>| argument.map(str::trim).filter(|argument| !argument.is_empty()).map(String::from);
+        let session = |name: &String| !name.starts_with('.') && !name.contains('/');
+        let command = match (parts.next(), argument(parts.next())) {
+            (Some("add_workspace"), Some(name)) => Some(Command::AddWorkspace(name)),
+            (Some("rename_workspace"), Some(name)) => Some(Command::RenameWorkspace(name)),
+            (Some("delete_workspace"), None) => Some(Command::DeleteWorkspace),
+            (Some("move_workspace_next"), None) => Some(Command::MoveWorkspaceNext),
+            (Some("move_workspace_previous"), None) => Some(Command::MoveWorkspacePrevious),
+            (Some("save_session"), Some(name)) if session(&name) => Some(Command::SaveSession(name)),
+            (Some("restore_session"), Some(name)) if session(&name) => Some(Command::RestoreSession(name)),
+            _ => None,
+        };
+        command.ok_or_else(|| Error::InvalidCommand(display.to_string()))
+    }
 }
 
 impl Command {
@@ -28,14 +90,173 @@ impl Command {
             KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key('k') }.into(),
             Command::PreviousWindow
         );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key('\t') }.into(),
+            Command::FocusLast
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key('[') }.into(),
+            Command::FocusHistoryBack
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key(']') }.into(),
+            Command::FocusHistoryForward
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone(), ModKey::Shift], key: Key('j') }.into(),
+            Command::SwapNext
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone(), ModKey::Shift], key: Key('k') }.into(),
+            Command::SwapPrevious
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key('s') }.into(),
+            Command::SwapMaster
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key('o') }.into(),
+            Command::RotateStack
+        );
         commands.insert(
             KeyCombo { mod_keys: vec![config.mod_key.clone(), ModKey::Shift], key: Key('q') }.into(),
             Command::Quit
         );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key('q') }.into(),
+            Command::Restart
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone(), ModKey::Shift], key: Key('s') }.into(),
+            Command::SaveSession("default".to_string())
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone(), ModKey::Shift], key: Key('o') }.into(),
+            Command::RestoreSession("default".to_string())
+        );
         commands.insert(
             KeyCombo { mod_keys: vec![config.mod_key.clone(), ModKey::Shift], key: Key('u') }.into(),
             Command::Spawn("urxvt".to_string())
         );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key('b') }.into(),
+            Command::PreselectSplit(Direction::Horizontal)
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key('v') }.into(),
+            Command::PreselectSplit(Direction::Vertical)
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key('e') }.into(),
+            Command::ToggleSplitDirection
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key('l') }.into(),
+            Command::ResizeSplit(0.1)
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key('h') }.into(),
+            Command::ResizeSplit(-0.1)
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone(), ModKey::Shift], key: Key('l') }.into(),
+            Command::ChangeRatio(0.05)
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone(), ModKey::Shift], key: Key('h') }.into(),
+            Command::ChangeRatio(-0.05)
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key('i') }.into(),
+            Command::ChangeMasterCount(1)
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key('d') }.into(),
+            Command::ChangeMasterCount(-1)
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone(), ModKey::Control], key: Key('j') }.into(),
+            Command::MoveWindowToNextSplit
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone(), ModKey::Control], key: Key('k') }.into(),
+            Command::MoveWindowToPreviousSplit
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key('r') }.into(),
+            Command::ToggleModifier(Modifier::ReflectHorizontal)
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone(), ModKey::Shift], key: Key('r') }.into(),
+            Command::ToggleModifier(Modifier::ReflectVertical)
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key('m') }.into(),
+            Command::ToggleModifier(Modifier::Magnify(1.5))
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone(), ModKey::Shift], key: Key('m') }.into(),
+            Command::ToggleModifier(Modifier::LimitWindows(2))
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key('`') }.into(),
+            Command::GoToPreviousWorkspace
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key('.') }.into(),
+            Command::NextWorkspace
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key(',') }.into(),
+            Command::PreviousWorkspace
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone(), ModKey::Shift], key: Key('.') }.into(),
+            Command::NextNonEmptyWorkspace
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone(), ModKey::Shift], key: Key(',') }.into(),
+            Command::PreviousNonEmptyWorkspace
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key('n') }.into(),
+            Command::FirstEmptyWorkspace
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone(), ModKey::Shift], key: Key('\u{8}') }.into(),
+            Command::DeleteWorkspace
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone(), ModKey::Control], key: Key('.') }.into(),
+            Command::MoveWorkspaceNext
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone(), ModKey::Control], key: Key(',') }.into(),
+            Command::MoveWorkspacePrevious
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key('=') }.into(),
+            Command::FocusNextScreen
+        );
+        commands.insert(
+            KeyCombo { mod_keys: vec![config.mod_key.clone()], key: Key('-') }.into(),
+            Command::FocusPreviousScreen
+        );
+        for pos in b'1'..=b'3' {
+            let index = usize::from(pos - b'1');
+            commands.insert(
+                KeyCombo { mod_keys: vec![config.mod_key.clone(), ModKey::Control], key: Key(char::from(pos)) }.into(),
+                Command::FocusScreen(index)
+            );
+            commands.insert(
+                KeyCombo { mod_keys: vec![config.mod_key.clone(), ModKey::Control, ModKey::Shift], key: Key(char::from(pos)) }.into(),
+                Command::MoveWindowToScreen(index)
+            );
+            commands.insert(
+                KeyCombo { mod_keys: vec![config.mod_key.clone(), ModKey::Mod1], key: Key(char::from(pos)) }.into(),
+                Command::SwapWorkspacesWithScreen(index)
+            );
+        }
         for pos in b'1'..=b'9' {
             let index = usize::from(pos - 49);
             let pos = char::from(pos);
@@ -46,6 +267,13 @@ impl Command {
         }
         commands
     }
+    pub fn new_buttons() -> HashMap<u8, Self> {
+        let mut commands = HashMap::new();
+        commands.insert(4, Command::PreviousWorkspace);
+        commands.insert(5, Command::NextWorkspace);
+        commands
+    }
+
     pub fn execute<W: Debug + Clone + Eq>(&self, state: State<W>) -> State<W> {
         match self {
             Command::Spawn(command) => {
@@ -54,8 +282,42 @@ impl Command {
             },
             Command::NextWindow => state.next_window(),
             Command::PreviousWindow => state.previous_window(),
+            Command::SwapNext => state.swap_next_window(),
+            Command::SwapPrevious => state.swap_previous_window(),
+            Command::SwapMaster => state.swap_master_window(),
+            Command::RotateStack => state.rotate_windows(),
+            Command::FocusLast => state.focus_last(),
+            Command::FocusHistoryBack => state.focus_history_back(),
+            Command::FocusHistoryForward => state.focus_history_forward(),
             Command::GoToWorkspace(index) => state.goto_workspace(*index),
-            Command::Quit => state.quit()
+            Command::GoToPreviousWorkspace => state.goto_previous_workspace(),
+            Command::NextWorkspace => state.cycle_workspace(true, false),
+            Command::PreviousWorkspace => state.cycle_workspace(false, false),
+            Command::NextNonEmptyWorkspace => state.cycle_workspace(true, true),
+            Command::PreviousNonEmptyWorkspace => state.cycle_workspace(false, true),
+            Command::FirstEmptyWorkspace => state.goto_first_empty_workspace(),
+            Command::AddWorkspace(name) => state.add_workspace(name.clone()),
+            Command::RenameWorkspace(name) => state.rename_workspace(name.clone()),
+            Command::DeleteWorkspace => state.delete_workspace(),
+            Command::MoveWorkspaceNext => state.move_workspace(true),
+            Command::MoveWorkspacePrevious => state.move_workspace(false),
+            Command::FocusNextScreen => state.cycle_screen(true),
+            Command::FocusPreviousScreen => state.cycle_screen(false),
+            Command::FocusScreen(screen) => state.focus_screen(*screen),
+            Command::MoveWindowToScreen(screen) => state.move_window_to_screen(*screen),
+            Command::SwapWorkspacesWithScreen(screen) => state.swap_workspaces_with_screen(*screen),
+            Command::PreselectSplit(direction) => state.preselect_split(*direction),
+            Command::ToggleSplitDirection => state.toggle_split_direction(),
+            Command::ResizeSplit(delta) => state.resize_split(*delta),
+            Command::MoveWindowToNextSplit => state.move_split_window(true),
+            Command::MoveWindowToPreviousSplit => state.move_split_window(false),
+            Command::ToggleModifier(modifier) => state.toggle_modifier(modifier.clone()),
+            Command::ChangeRatio(delta) => state.change_ratio(*delta),
+            Command::ChangeMasterCount(delta) => state.change_master_count(*delta),
+            Command::SaveSession(name) => state.request_session(SessionRequest::Save(name.clone())),
+            Command::RestoreSession(name) => state.request_session(SessionRequest::Restore(name.clone())),
+            Command::Quit => state.quit(),
+            Command::Restart => state.restart(),
         }
     }
 }
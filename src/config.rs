@@ -1,19 +1,172 @@
 use crate::layouts::Layout;
 use crate::keys::ModKey;
+use crate::layouts::modifiers::Modifier;
+use std::collections::HashMap;
+use crate::stack::FocusPolicy;
+use crate::error::{Error, Result};
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
 
 #[derive(Clone)]
 pub struct Config {
     pub mod_key: ModKey,
     pub workspaces: Vec<String>,
     pub layouts: Vec<Layout>,
+    pub modifiers: HashMap<String, Vec<Modifier>>,
+    pub focus_policy: FocusPolicy,
+    pub workspace_auto_back_and_forth: bool,
+    pub swap_visible_workspaces: bool,
+    pub warp_pointer: bool,
+    pub tab_focused_color: u32,
+    pub tab_unfocused_color: u32,
+    pub tab_text_color: u32,
+    /// X display to connect to, `$DISPLAY` when unset.
+    pub display: Option<String>,
+    /// Take over the screen from a running window manager.
+    pub replace: bool,
+    /// Where named sessions are saved.
+    pub session_dir: Option<PathBuf>,
+    /// Run the command of the saved windows missing when restoring a session,
+    /// their windows go to the saved workspace when they show up.
+    pub launch_missing_windows: bool,
 }
 
 impl Config {
     pub fn new() -> Self {
         Self {
             mod_key: ModKey::Mod4,
-            layouts: vec!["tall".into(), "fullscreen".into()],
-            workspaces: (1..9).into_iter().map(|i| i.to_string()).collect(),
+            layouts: vec![Layout::Tall, Layout::FullScreen],
+            modifiers: HashMap::new(),
+            focus_policy: FocusPolicy::Previous,
+            workspace_auto_back_and_forth: false,
+            swap_visible_workspaces: false,
+            warp_pointer: true,
+            workspaces: (1..=9).map(|i| i.to_string()).collect(),
+            tab_focused_color: 0x005577,
+            tab_unfocused_color: 0x222222,
+            tab_text_color: 0xeeeeee,
+            display: None,
+            replace: false,
+            session_dir: Self::default_session_dir(),
+            launch_missing_windows: false,
         }
     }
+
+    /// `$XDG_DATA_HOME/rwm/sessions`, falling back to `~/.local/share/rwm/sessions`.
+    fn default_session_dir() -> Option<PathBuf> {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("share")))
+            .map(|dir| dir.join("rwm").join("sessions"))
+    }
+
+    /// `$XDG_CONFIG_HOME/rwm/config`, falling back to `~/.config/rwm/config`.
+    pub fn default_path() -> Option<PathBuf> {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+            .map(|dir| dir.join("rwm").join("config"))
+    }
+
+    /// Reads a file of `key = value` lines on top of the defaults. Lists are
+    /// separated by spaces and `#` starts a comment.
+    pub fn load(path: &Path) -> Result<Self> {
+        fs::read_to_string(path)
+            .map_err(|error| error.to_string())
+            .and_then(|content| Self::new().parse(&content))
+            .map_err(|message| Error::InvalidConfig(format!("{}: {}", path.display(), message)))
+    }
+
+    fn parse(self, content: &str) -> std::result::Result<Self, String> {
+        let config = content.lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.split('#').next().unwrap_or("").trim()))
+            .filter(|(_, line)| !line.is_empty())
+            .try_fold(self, |config, (number, line)| {
+                config.set(line).map_err(|error| format!("line {}: {}", number, error))
+            })?;
+        if config.workspaces.is_empty() {
+            return Err("no workspaces".to_string());
+        }
+        if config.layouts.is_empty() {
+            return Err("no layouts".to_string());
+        }
+        Ok(config)
+    }
+
+    fn set(mut self, line: &str) -> std::result::Result<Self, String> {
+        let mut parts = line.splitn(2, '=').map(str::trim);
+        let (key, value) = match (parts.next(), parts.next()) {
+            (Some(key), Some(value)) => (key, value),
+            _ => return Err(format!("expected `key = value`, found {:?}", line)),
+        };
+        let values = value.split_whitespace();
+        match key {
+            "mod_key" => self.mod_key = ModKey::try_from(value).map_err(|e| e.to_string())?,
+            "workspaces" => self.workspaces = values.map(String::from).collect(),
+            "layouts" => self.layouts = values.map(Layout::try_from).collect::<Result<_>>().map_err(|e| e.to_string())?,
+            "focus_policy" => self.focus_policy = FocusPolicy::try_from(value).map_err(|e| e.to_string())?,
+            "workspace_auto_back_and_forth" => self.workspace_auto_back_and_forth = parse_bool(value)?,
+            "swap_visible_workspaces" => self.swap_visible_workspaces = parse_bool(value)?,
+            "warp_pointer" => self.warp_pointer = parse_bool(value)?,
+            "tab_focused_color" => self.tab_focused_color = parse_color(value)?,
+            "tab_unfocused_color" => self.tab_unfocused_color = parse_color(value)?,
+            "tab_text_color" => self.tab_text_color = parse_color(value)?,
+            "session_dir" => self.session_dir = Some(PathBuf::from(value)),
+            "launch_missing_windows" => self.launch_missing_windows = parse_bool(value)?,
+            _ if key.starts_with("modifiers.") => {
+                let modifiers = values.map(Modifier::try_from).collect::<Result<_>>().map_err(|e| e.to_string())?;
+                self.modifiers.insert(key["modifiers.".len()..].to_string(), modifiers);
+            },
+            _ => return Err(format!("unknown option {:?}", key)),
+        }
+        Ok(self)
+    }
+}
+
+fn parse_bool(value: &str) -> std::result::Result<bool, String> {
+    value.parse().map_err(|_| format!("expected true or false, found {:?}", value))
+}
+
+fn parse_color(value: &str) -> std::result::Result<u32, String> {
+    let hex = value.trim_start_matches("0x");
+    u32::from_str_radix(hex, 16).map_err(|_| format!("invalid color {:?}", value))
+}
+
+#[cfg(test)]
+mod config_tests {
+    use crate::config::Config;
+    use crate::keys::ModKey;
+    use crate::layouts::Layout;
+    use crate::layouts::modifiers::Modifier;
+    use crate::stack::FocusPolicy;
+
+    #[test]
+    fn test() {
+        let config = Config::new().parse("
+            # comments and blank lines are skipped
+            mod_key = mod1
+            workspaces = web code chat
+            layouts = grid tabbed # trailing comment
+            focus_policy = most_recent
+            warp_pointer = false
+            tab_focused_color = 0xff0000
+            modifiers.web = reflect_horizontal magnify:1.5
+        ").unwrap();
+        assert_eq!(ModKey::Mod1, config.mod_key);
+        assert_eq!(vec!["web", "code", "chat"], config.workspaces);
+        assert_eq!(vec![Layout::Grid, Layout::Tabbed], config.layouts);
+        assert_eq!(FocusPolicy::MostRecent, config.focus_policy);
+        assert!(!config.warp_pointer);
+        assert_eq!(0xff0000, config.tab_focused_color);
+        assert_eq!(Some(&vec![Modifier::ReflectHorizontal, Modifier::Magnify(1.5)]), config.modifiers.get("web"));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(Some("line 2: invalid layout \"tiled\"".to_string()), Config::new().parse("\nlayouts = tiled").err());
+        assert_eq!(Some("line 1: unknown option \"gaps\"".to_string()), Config::new().parse("gaps = 4").err());
+        assert_eq!(Some("no workspaces".to_string()), Config::new().parse("workspaces =").err());
+    }
 }
@@ -0,0 +1,127 @@
+use crate::displays::{DisplayServer, Event};
+use crate::config::Config;
+use crate::window::{Window, Geometry};
+use crate::keys::KeyCombo;
+use crate::error::Result;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::ops::Deref;
+use futures::Stream;
+use futures::task::{Context, Poll};
+use std::pin::Pin;
+
+/// Every request the manager sent to the display, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Call {
+    ConfigureWindow(u32, Geometry),
+    SetVisibility(u32, bool),
+    RaiseWindow(u32),
+    FocusWindow(Option<u32>),
+    WarpPointer(Geometry),
+    ShowTabBar(String, Geometry, Vec<(u32, String, bool)>),
+    HideTabBar(String),
+    SetWorkspaces(Vec<String>, usize),
+    Quit,
+}
+
+/// In-memory display server replaying a scripted list of events, the stream
+/// ends when the script runs out or when the manager quits.
+#[derive(Clone, Default)]
+pub struct MockDisplayServer {
+    events: Rc<RefCell<VecDeque<Event<u32, KeyCombo>>>>,
+    calls: Rc<RefCell<Vec<Call>>>,
+    windows: Vec<(u32, bool)>,
+}
+
+impl MockDisplayServer {
+    pub fn with_events(events: Vec<Event<u32, KeyCombo>>) -> Self {
+        Self {
+            events: Rc::new(RefCell::new(events.into())),
+            calls: Rc::new(RefCell::new(Vec::new())),
+            windows: Vec::new(),
+        }
+    }
+
+    /// Windows that exist before the manager starts.
+    pub fn with_windows(mut self, windows: Vec<(u32, bool)>) -> Self {
+        self.windows = windows;
+        self
+    }
+
+    pub fn calls(&self) -> Vec<Call> {
+        self.calls.borrow().clone()
+    }
+
+    fn record(&self, call: Call) {
+        self.calls.borrow_mut().push(call);
+    }
+}
+
+impl Stream for MockDisplayServer {
+    type Item = Event<u32, KeyCombo>;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.events.borrow_mut().pop_front() {
+            Some(Event::DisplayEnded) | None => Poll::Ready(None),
+            event => Poll::Ready(event),
+        }
+    }
+}
+
+impl DisplayServer for MockDisplayServer {
+    type Window = u32;
+    type KeyCombo = KeyCombo;
+
+    fn new(_config: &Config) -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn get_windows(&self) -> Vec<(u32, bool)> {
+        self.windows.clone()
+    }
+
+    fn get_command(&self, _window: &u32) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn configure_window(&self, window: &Window<u32>) {
+        if let Some(view) = window.get_view() {
+            self.record(Call::ConfigureWindow(*window.deref(), view.clone()));
+        }
+    }
+
+    fn set_visibility(&self, window: &u32, show: bool) {
+        self.record(Call::SetVisibility(*window, show));
+    }
+
+    fn raise_window(&self, window: &u32) {
+        self.record(Call::RaiseWindow(*window));
+    }
+
+    fn focus_window(&self, window: Option<&u32>) {
+        self.record(Call::FocusWindow(window.cloned()));
+    }
+
+    fn warp_pointer(&self, view: &Geometry) {
+        self.record(Call::WarpPointer(view.clone()));
+    }
+
+    fn show_tab_bar(&self, workspace: &str, view: &Geometry, tabs: &[(u32, String, bool)]) {
+        self.record(Call::ShowTabBar(workspace.to_string(), view.clone(), tabs.to_vec()));
+    }
+
+    fn hide_tab_bar(&self, workspace: &str) {
+        self.record(Call::HideTabBar(workspace.to_string()));
+    }
+
+    fn set_workspaces(&self, names: &[&str], current: usize) {
+        let names = names.iter().map(|name| name.to_string()).collect();
+        self.record(Call::SetWorkspaces(names, current));
+    }
+
+    fn quit(&self) {
+        self.record(Call::Quit);
+        self.events.borrow_mut().push_front(Event::DisplayEnded);
+    }
+}
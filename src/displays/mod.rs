@@ -1,20 +1,30 @@
-use crate::window::{Window, WindowType, Geometry};
+use crate::window::{Window, WindowType, Geometry, WindowProperties};
 use std::fmt::Debug;
 use crate::keys::KeyCombo;
 use std::hash::Hash;
 use crate::config::Config;
 use futures::Stream;
+use crate::error::Result;
 
 pub mod xcb_server;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
 
 #[derive(Debug, Eq, PartialEq)]
 pub enum Event<W, K> {
     DisplayInited,
     ScreenAdded(W, Geometry),
+    ScreenRemoved(W),
+    ScreenChanged(W, Geometry),
     KeyPressed(K),
+    RootButtonPressed(u8),
     WindowAdded(W, WindowType),
     WindowRemoved(W),
     WindowFocused(W),
+    /// Sent after a window is added and whenever its class, title or role change.
+    WindowPropertyChanged(W, WindowProperties),
+    /// A command sent with `rwm --command`.
+    CommandReceived(String),
     DisplayEnded,
     Ignored,
 }
@@ -22,8 +32,19 @@ pub enum Event<W, K> {
 pub trait DisplayServer: Stream<Item=Event<<Self as DisplayServer>::Window, <Self as DisplayServer>::KeyCombo>> + Clone {
     type Window: Debug + Clone + Eq;
     type KeyCombo: From<KeyCombo> + Hash + Eq + Debug;
-    fn new(config: &Config) -> Self;
+    fn new(config: &Config) -> Result<Self>;
+    /// The top-level windows already on the display and whether they are mapped.
+    fn get_windows(&self) -> Vec<(Self::Window, bool)>;
+    /// The command line of the process owning the window, when it is known.
+    fn get_command(&self, window: &Self::Window) -> Vec<String>;
     fn configure_window(&self, window: &Window<Self::Window>);
     fn set_visibility(&self, window: &Self::Window, show: bool);
+    fn raise_window(&self, window: &Self::Window);
+    /// Gives the keyboard focus to the window, or to the root window when there is none.
+    fn focus_window(&self, window: Option<&Self::Window>);
+    fn warp_pointer(&self, view: &Geometry);
+    fn show_tab_bar(&self, workspace: &str, view: &Geometry, tabs: &[(Self::Window, String, bool)]);
+    fn hide_tab_bar(&self, workspace: &str);
+    fn set_workspaces(&self, names: &[&str], current: usize);
     fn quit(&self);
 }
@@ -1,6 +1,9 @@
 use crate::displays::{DisplayServer, Event};
 use crate::config::Config;
-use crate::window::{WindowType, Geometry, Window};
+use crate::error::{Error, Result};
+use xcb_util::icccm;
+use std::fs;
+use crate::window::{WindowType, Geometry, Window, WindowProperties};
 use std::rc::Rc;
 use xcb_util::ewmh;
 use xcb_util::keysyms::KeySymbols;
@@ -10,31 +13,117 @@ use std::ops::Deref;
 use futures::Stream;
 use futures::task::{Context, Poll};
 use std::pin::Pin;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::io;
+use std::os::unix::io::{AsRawFd, RawFd};
+use tokio::io::PollEvented;
+use mio::{Evented, Poll as MioPoll, PollOpt, Ready, Token};
+use mio::unix::EventedFd;
+use std::thread;
+use std::time::{Duration, Instant};
 
 #[derive(Clone)]
 pub struct XcbDisplayServer {
     connection: Rc<ewmh::Connection>,
+    fd: Rc<PollEvented<ConnectionFd>>,
     events: Rc<RefCell<Vec<Event<xcb::Window, XcbKeyCombo>>>>,
+    tab_bars: Rc<RefCell<HashMap<String, TabBar>>>,
+    roots: Vec<xcb::Window>,
+    /// Windows owning the `WM_Sn` selection of each root.
+    selections: Vec<xcb::Window>,
+    /// The screens by the id given to the window manager, which stays the
+    /// same as long as the monitor or its area is found again.
+    screens: Rc<RefCell<Vec<(u32, Monitor, Geometry)>>>,
+    randr_event: Option<u8>,
+    window_role: xcb::Atom,
+    command_property: xcb::Atom,
+    tab_focused_color: u32,
+    tab_unfocused_color: u32,
+    tab_text_color: u32,
+}
+
+/// The X connection socket, registered with the tokio reactor so the event
+/// stream is woken up when the server sends something.
+struct ConnectionFd(RawFd);
+
+impl Evented for ConnectionFd {
+    fn register(&self, poll: &MioPoll, token: Token, interest: Ready, opts: PollOpt) -> io::Result<()> {
+        EventedFd(&self.0).register(poll, token, interest, opts)
+    }
+
+    fn reregister(&self, poll: &MioPoll, token: Token, interest: Ready, opts: PollOpt) -> io::Result<()> {
+        EventedFd(&self.0).reregister(poll, token, interest, opts)
+    }
+
+    fn deregister(&self, poll: &MioPoll) -> io::Result<()> {
+        EventedFd(&self.0).deregister(poll)
+    }
+}
+
+/// Root window property `rwm --command` appends its commands to, one per line.
+const COMMAND_PROPERTY: &str = "_RWM_COMMAND";
+
+/// Events selected on the managed windows.
+const CLIENT_EVENT_MASK: u32 = xcb::EVENT_MASK_BUTTON_PRESS |
+    xcb::EVENT_MASK_BUTTON_RELEASE |
+    xcb::EVENT_MASK_KEY_PRESS |
+    xcb::EVENT_MASK_PROPERTY_CHANGE;
+
+/// Where a monitor was found. CRTC ids, Xinerama screen numbers and root
+/// windows are unrelated numbers, so they are not handed out as screen ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Monitor {
+    Crtc(xcb::randr::Crtc),
+    Xinerama(u32),
+    Root(xcb::Window),
+}
+
+struct TabBar {
+    window: xcb::Window,
+    gc: xcb::Gcontext,
+    view: Geometry,
+    /// The tabs with the titles they show, so redrawing does not ask the server.
+    tabs: Vec<(xcb::Window, String, bool)>,
 }
 
 impl Stream for XcbDisplayServer {
     type Item = Event<xcb::Window, XcbKeyCombo>;
 
-    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        self.connection.flush();
-        let mut events = self.events.borrow_mut();
-        if events.len() == 0 {
-            match self.connection.wait_for_event() {
-                Some(event) => Poll::Ready(Some(self.match_event(event))),
-                None => Poll::Pending,
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            self.connection.flush();
+            let queued = {
+                let mut events = self.events.borrow_mut();
+                if events.is_empty() { None } else { Some(events.remove(0)) }
+            };
+            match queued {
+                Some(Event::DisplayEnded) => return Poll::Ready(None),
+                Some(event) => return Poll::Ready(Some(event)),
+                None => {},
             }
-        } else {
-            let event = events.remove(0);
-            if event == Event::DisplayEnded { return Poll::Ready(None) }
+            // xcb may already have read events off the socket, so drain them
+            // before waiting for the socket to become readable again
             if let Some(event) = self.connection.poll_for_event() {
-                events.push(self.match_event(event));
-            };
-            Poll::Ready(Some(event))
+                return Poll::Ready(Some(self.match_event(event)));
+            }
+            if let Err(error) = self.connection.has_error() {
+                log::error!("Lost connection to the display {:?}", error);
+                return Poll::Ready(None);
+            }
+            match self.fd.poll_read_ready(cx, Ready::readable()) {
+                Poll::Ready(Ok(_)) => {
+                    if let Err(error) = self.fd.clear_read_ready(cx, Ready::readable()) {
+                        log::error!("Failed to wait for the display {:?}", error);
+                        return Poll::Ready(None);
+                    }
+                },
+                Poll::Ready(Err(error)) => {
+                    log::error!("Failed to wait for the display {:?}", error);
+                    return Poll::Ready(None);
+                },
+                Poll::Pending => return Poll::Pending,
+            }
         }
     }
 }
@@ -43,9 +132,10 @@ impl DisplayServer for XcbDisplayServer {
     type Window = xcb::Window;
     type KeyCombo = XcbKeyCombo;
 
-    fn new(_config: &Config) -> Self {
-        let (connection, screen_num) = xcb::Connection::connect(None).unwrap();
-        let connection = ewmh::Connection::connect(connection).map_err(|e| e.0).unwrap();
+    fn new(config: &Config) -> Result<Self> {
+        let (connection, _) = xcb::Connection::connect(config.display.as_deref())?;
+        let connection = ewmh::Connection::connect(connection)
+            .map_err(|(error, _)| Error::from(error))?;
         let setup = connection.get_setup();
 
         let events = [(
@@ -55,29 +145,101 @@ impl DisplayServer for XcbDisplayServer {
                 xcb::EVENT_MASK_KEY_PRESS |
                 xcb::EVENT_MASK_EXPOSURE |
                 xcb::EVENT_MASK_SUBSTRUCTURE_REDIRECT |
-                xcb::EVENT_MASK_SUBSTRUCTURE_NOTIFY,
+                xcb::EVENT_MASK_SUBSTRUCTURE_NOTIFY |
+                xcb::EVENT_MASK_PROPERTY_CHANGE,
         )];
 
-        let screens = setup.roots()
+        let roots = setup.roots()
             .map(|screen| screen.root())
-            .map(|screen| {
-                let cookie = xcb::change_window_attributes(&connection, screen, &events);
-                if !cookie.request_check().is_ok() {
-                    panic!("There's another Window Manager Running!");
-                }
-                screen
-            })
-            .map(|screen| Event::ScreenAdded(screen, Self::get_screen_view(&connection, screen)))
+            .collect::<Vec<_>>();
+        let selections = roots.iter()
+            .enumerate()
+            .map(|(screen, root)| Self::acquire_selection(&connection, *root, screen, config.replace))
+            .collect::<Result<Vec<_>>>()?;
+        roots.iter().try_for_each(|root| {
+            xcb::change_window_attributes_checked(&connection, *root, &events)
+                .request_check()
+                .map_err(|_| Error::OtherWindowManager)
+        })?;
+        let supported = [
+            connection.SUPPORTED(),
+            connection.NUMBER_OF_DESKTOPS(),
+            connection.CURRENT_DESKTOP(),
+            connection.DESKTOP_NAMES(),
+            connection.ACTIVE_WINDOW(),
+        ];
+        (0..roots.len()).for_each(|screen| { ewmh::set_supported(&connection, screen as i32, &supported); });
+        let randr_event = connection.get_extension_data(xcb::randr::id())
+            .filter(|data| data.present())
+            .map(|data| data.first_event());
+        if randr_event.is_some() {
+            let mask = xcb::randr::NOTIFY_MASK_SCREEN_CHANGE | xcb::randr::NOTIFY_MASK_CRTC_CHANGE;
+            roots.iter().for_each(|root| { xcb::randr::select_input(&connection, *root, mask as u16); });
+        }
+        let monitors = roots.iter()
+            .flat_map(|root| Self::get_screen_views(&connection, *root))
             .collect();
+        let screens = assign_screen_ids(&[], monitors);
+        let events = screens.iter()
+            .map(|(screen, _, view)| Event::ScreenAdded(*screen, view.clone()))
+            .collect();
+
+        let window_role = xcb::intern_atom(&connection, false, "WM_WINDOW_ROLE").get_reply()?.atom();
+        let command_property = xcb::intern_atom(&connection, false, COMMAND_PROPERTY).get_reply()?.atom();
+        let fd = PollEvented::new(ConnectionFd(connection.as_raw_fd()))?;
 
-        XcbDisplayServer {
+        Ok(XcbDisplayServer {
             connection: Rc::new(connection),
-            events: Rc::new(RefCell::new(screens)),
-        }
+            fd: Rc::new(fd),
+            events: Rc::new(RefCell::new(events)),
+            tab_bars: Rc::new(RefCell::new(HashMap::new())),
+            roots,
+            selections,
+            screens: Rc::new(RefCell::new(screens)),
+            randr_event,
+            window_role,
+            command_property,
+            tab_focused_color: config.tab_focused_color,
+            tab_unfocused_color: config.tab_unfocused_color,
+            tab_text_color: config.tab_text_color,
+        })
+    }
+
+    fn get_windows(&self) -> Vec<(xcb::Window, bool)> {
+        let tab_bars = self.tab_bars.borrow();
+        self.roots.iter()
+            .filter_map(|root| xcb::query_tree(&self.connection, *root).get_reply().ok())
+            .flat_map(|tree| tree.children().to_vec())
+            .filter(|window| !self.selections.contains(window))
+            .filter(|window| tab_bars.values().all(|tab_bar| tab_bar.window != *window))
+            .filter_map(|window| {
+                let attributes = xcb::get_window_attributes(&self.connection, window).get_reply().ok()?;
+                let is_managed = !attributes.override_redirect() && attributes.class() == xcb::WINDOW_CLASS_INPUT_OUTPUT as u16;
+                let is_mapped = attributes.map_state() == xcb::MAP_STATE_VIEWABLE as u8;
+                if is_managed { Some((window, is_mapped)) } else { None }
+            })
+            .inspect(|(window, _)| self.watch_window(*window))
+            .collect()
+    }
+
+    /// Read from `/proc`, so only for processes running on this machine.
+    fn get_command(&self, window: &xcb::Window) -> Vec<String> {
+        ewmh::get_wm_pid(&self.connection, *window).get_reply().ok()
+            .and_then(|pid| fs::read(format!("/proc/{}/cmdline", pid)).ok())
+            .map(|cmdline| {
+                cmdline.split(|byte| *byte == 0)
+                    .filter(|arg| !arg.is_empty())
+                    .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                    .collect()
+            })
+            .unwrap_or_default()
     }
 
     fn configure_window(&self, window: &Window<xcb::Window>) {
-        let view = window.get_view();
+        let view = match window.get_view() {
+            Some(view) => view,
+            None => return,
+        };
         let values = [
             (xcb::CONFIG_WINDOW_X as u16, view.position.x as u32),
             (xcb::CONFIG_WINDOW_Y as u16, view.position.y as u32),
@@ -86,13 +248,7 @@ impl DisplayServer for XcbDisplayServer {
         ];
         let window_id = window.deref();
         xcb::configure_window(&self.connection, *window_id, &values);
-        let events = [(
-            xcb::CW_EVENT_MASK,
-            xcb::EVENT_MASK_BUTTON_PRESS |
-                xcb::EVENT_MASK_BUTTON_RELEASE |
-                xcb::EVENT_MASK_KEY_PRESS
-        )];
-        xcb::change_window_attributes(&self.connection, *window_id, &events);
+        xcb::change_window_attributes(&self.connection, *window_id, &[(xcb::CW_EVENT_MASK, CLIENT_EVENT_MASK)]);
     }
 
     fn set_visibility(&self, window: &xcb::Window, show: bool) {
@@ -103,24 +259,388 @@ impl DisplayServer for XcbDisplayServer {
         }
     }
 
+    fn raise_window(&self, window: &xcb::Window) {
+        let values = [(xcb::CONFIG_WINDOW_STACK_MODE as u16, xcb::STACK_MODE_ABOVE)];
+        xcb::configure_window(&self.connection, *window, &values);
+    }
+
+    fn focus_window(&self, window: Option<&xcb::Window>) {
+        let focus = window.cloned().unwrap_or(xcb::INPUT_FOCUS_POINTER_ROOT);
+        xcb::set_input_focus(&self.connection, xcb::INPUT_FOCUS_POINTER_ROOT as u8, focus, xcb::CURRENT_TIME);
+        (0..self.roots.len() as i32).for_each(|screen| {
+            ewmh::set_active_window(&self.connection, screen, window.cloned().unwrap_or(xcb::NONE));
+        });
+    }
+
+    fn warp_pointer(&self, view: &Geometry) {
+        let x = view.position.x + view.size.width as i32 / 2;
+        let y = view.position.y + view.size.height as i32 / 2;
+        xcb::warp_pointer(&self.connection, xcb::NONE, self.roots[0], 0, 0, 0, 0, x as i16, y as i16);
+    }
+
+    fn show_tab_bar(&self, workspace: &str, view: &Geometry, tabs: &[(xcb::Window, String, bool)]) {
+        let mut tab_bars = self.tab_bars.borrow_mut();
+        let tab_bar = match tab_bars.entry(workspace.to_string()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => match self.create_tab_bar() {
+                Ok(tab_bar) => entry.insert(tab_bar),
+                Err(error) => {
+                    log::error!("Failed to create the tab bar of workspace {}: {}", workspace, error);
+                    return;
+                },
+            },
+        };
+        tab_bar.view = view.clone();
+        tab_bar.tabs = tabs.to_vec();
+        let values = [
+            (xcb::CONFIG_WINDOW_X as u16, view.position.x as u32),
+            (xcb::CONFIG_WINDOW_Y as u16, view.position.y as u32),
+            (xcb::CONFIG_WINDOW_WIDTH as u16, view.size.width),
+            (xcb::CONFIG_WINDOW_HEIGHT as u16, view.size.height),
+            (xcb::CONFIG_WINDOW_STACK_MODE as u16, xcb::STACK_MODE_ABOVE),
+        ];
+        xcb::configure_window(&self.connection, tab_bar.window, &values);
+        xcb::map_window(&self.connection, tab_bar.window);
+        self.draw_tab_bar(tab_bar);
+    }
+
+    fn hide_tab_bar(&self, workspace: &str) {
+        if let Some(tab_bar) = self.tab_bars.borrow().get(workspace) {
+            xcb::unmap_window(&self.connection, tab_bar.window);
+        }
+    }
+
+    fn set_workspaces(&self, names: &[&str], current: usize) {
+        (0..self.roots.len() as i32).for_each(|screen| {
+            ewmh::set_number_of_desktops(&self.connection, screen, names.len() as u32);
+            ewmh::set_desktop_names(&self.connection, screen, names.iter().cloned());
+            ewmh::set_current_desktop(&self.connection, screen, current as u32);
+        });
+        // Tab bars of workspaces which were renamed or deleted are not needed anymore
+        self.tab_bars.borrow_mut().retain(|workspace, tab_bar| {
+            let keep = names.contains(&workspace.as_str());
+            if !keep {
+                xcb::free_gc(&self.connection, tab_bar.gc);
+                xcb::destroy_window(&self.connection, tab_bar.window);
+            }
+            keep
+        });
+    }
+
     fn quit(&self) {
+        // stop redirecting before giving up the selection, so that a replacing
+        // window manager can take over as soon as the owner window is gone
+        let events = [(xcb::CW_EVENT_MASK, xcb::EVENT_MASK_NO_EVENT)];
+        self.roots.iter().for_each(|root| { xcb::change_window_attributes(&self.connection, *root, &events); });
+        self.selections.iter().for_each(|window| { xcb::destroy_window(&self.connection, *window); });
+        self.connection.flush();
         self.events.borrow_mut().push(Event::DisplayEnded)
     }
 }
 
 impl XcbDisplayServer {
-    fn get_screen_view(connection: &xcb::Connection, screen: u32) -> Geometry {
-        let reply = xcb::get_geometry(connection, screen)
+    /// Hands a command to the window manager running on the display.
+    pub fn send_command(display: Option<&str>, command: &str) -> Result<()> {
+        let (connection, screen) = xcb::Connection::connect(display)?;
+        let root = connection.get_setup().roots().nth(screen as usize)
+            .map(|screen| screen.root())
+            .ok_or(Error::NoScreen)?;
+        let property = xcb::intern_atom(&connection, false, COMMAND_PROPERTY).get_reply()?.atom();
+        let command = format!("{}\n", command.trim());
+        xcb::change_property_checked(&connection, xcb::PROP_MODE_APPEND as u8, root, property, xcb::ATOM_STRING, 8, command.as_bytes())
+            .request_check()?;
+        Ok(())
+    }
+
+    /// Takes the ICCCM `WM_Sn` manager selection of a screen. When replacing,
+    /// waits for the current owner to destroy its window before going on.
+    fn acquire_selection(connection: &xcb::Connection, root: xcb::Window, screen: usize, replace: bool) -> Result<xcb::Window> {
+        let selection = xcb::intern_atom(connection, false, &format!("WM_S{}", screen)).get_reply()?.atom();
+        let previous = xcb::get_selection_owner(connection, selection).get_reply()?.owner();
+        if previous != xcb::NONE {
+            if !replace {
+                return Err(Error::OtherWindowManager);
+            }
+            let events = [(xcb::CW_EVENT_MASK, xcb::EVENT_MASK_STRUCTURE_NOTIFY)];
+            xcb::change_window_attributes(connection, previous, &events);
+        }
+
+        let window = connection.generate_id();
+        let values = [(xcb::CW_EVENT_MASK, xcb::EVENT_MASK_PROPERTY_CHANGE)];
+        xcb::create_window(
+            connection,
+            xcb::COPY_FROM_PARENT as u8,
+            window,
+            root,
+            -1, -1, 1, 1, 0,
+            xcb::WINDOW_CLASS_INPUT_ONLY as u16,
+            xcb::COPY_FROM_PARENT,
+            &values,
+        );
+        let time = Self::get_timestamp(connection, window);
+        xcb::set_selection_owner(connection, window, selection, time);
+        if xcb::get_selection_owner(connection, selection).get_reply()?.owner() != window {
+            return Err(Error::OtherWindowManager);
+        }
+        if previous != xcb::NONE && !Self::wait_for_destroy(connection, previous) {
+            log::warn!("The previous window manager did not exit");
+            return Err(Error::OtherWindowManager);
+        }
+
+        let manager = xcb::intern_atom(connection, false, "MANAGER").get_reply()?.atom();
+        let data = xcb::ClientMessageData::from_data32([time, selection, window, 0, 0]);
+        let event = xcb::ClientMessageEvent::new(32, root, manager, data);
+        xcb::send_event(connection, false, root, xcb::EVENT_MASK_STRUCTURE_NOTIFY, &event);
+        Ok(window)
+    }
+
+    /// Gets a server timestamp from an empty property change, as selections
+    /// should not be taken with `CurrentTime`.
+    fn get_timestamp(connection: &xcb::Connection, window: xcb::Window) -> xcb::Timestamp {
+        xcb::change_property(connection, xcb::PROP_MODE_APPEND as u8, window, xcb::ATOM_WM_NAME, xcb::ATOM_STRING, 8, &[] as &[u8]);
+        connection.flush();
+        while let Some(event) = connection.wait_for_event() {
+            if event.response_type() == xcb::PROPERTY_NOTIFY {
+                let property_notify: &xcb::PropertyNotifyEvent = unsafe { xcb::cast_event(&event) };
+                if property_notify.window() == window {
+                    return property_notify.time();
+                }
+            }
+        }
+        xcb::CURRENT_TIME
+    }
+
+    fn wait_for_destroy(connection: &xcb::Connection, window: xcb::Window) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        connection.flush();
+        while Instant::now() < deadline && connection.has_error().is_ok() {
+            match connection.poll_for_event() {
+                Some(event) if event.response_type() == xcb::DESTROY_NOTIFY => {
+                    let destroy_event: &xcb::DestroyNotifyEvent = unsafe { xcb::cast_event(&event) };
+                    if destroy_event.window() == window {
+                        return true;
+                    }
+                },
+                Some(_) => {},
+                None => thread::sleep(Duration::from_millis(10)),
+            }
+        }
+        false
+    }
+
+    /// Finds the monitors of a root window with RandR, Xinerama or else the
+    /// root window itself.
+    fn get_screen_views(connection: &xcb::Connection, root: xcb::Window) -> Vec<(Monitor, Geometry)> {
+        let views = Self::get_randr_views(connection, root);
+        if !views.is_empty() { return views; }
+        let views = Self::get_xinerama_views(connection);
+        if !views.is_empty() { return views; }
+        match Self::get_screen_view(connection, root) {
+            Ok(view) => vec![(Monitor::Root(root), view)],
+            Err(error) => {
+                log::error!("Failed to get the size of screen {}: {}", root, error);
+                Vec::new()
+            },
+        }
+    }
+
+    fn has_extension(connection: &xcb::Connection, extension: &mut xcb::Extension) -> bool {
+        connection.get_extension_data(extension)
+            .is_some_and(|data| data.present())
+    }
+
+    fn get_randr_views(connection: &xcb::Connection, root: xcb::Window) -> Vec<(Monitor, Geometry)> {
+        if !Self::has_extension(connection, xcb::randr::id()) { return Vec::new(); }
+        let resources = match xcb::randr::get_screen_resources_current(connection, root).get_reply() {
+            Ok(resources) => resources,
+            Err(_) => return Vec::new(),
+        };
+        let mut views: Vec<(Monitor, Geometry)> = Vec::new();
+        resources.crtcs().iter()
+            .filter_map(|crtc| {
+                let info = xcb::randr::get_crtc_info(connection, *crtc, resources.config_timestamp())
+                    .get_reply()
+                    .ok()?;
+                if info.mode() == xcb::NONE || info.num_outputs() == 0 { return None; }
+                let view = Geometry::new(i32::from(info.x()), i32::from(info.y()), u32::from(info.width()), u32::from(info.height()));
+                Some((*crtc, view))
+            })
+            .for_each(|(crtc, view)| {
+                // Cloned outputs show the same area, keep only one screen for them
+                if !views.iter().any(|(_, other)| other == &view) {
+                    views.push((Monitor::Crtc(crtc), view));
+                }
+            });
+        views
+    }
+
+    fn get_xinerama_views(connection: &xcb::Connection) -> Vec<(Monitor, Geometry)> {
+        if !Self::has_extension(connection, xcb::xinerama::id()) { return Vec::new(); }
+        let is_active = xcb::xinerama::is_active(connection).get_reply()
+            .is_ok_and(|reply| reply.state() != 0);
+        if !is_active { return Vec::new(); }
+        xcb::xinerama::query_screens(connection).get_reply()
+            .map(|reply| {
+                reply.screen_info()
+                    .enumerate()
+                    .map(|(index, info)| {
+                        let view = Geometry::new(i32::from(info.x_org()), i32::from(info.y_org()), u32::from(info.width()), u32::from(info.height()));
+                        (Monitor::Xinerama(index as u32), view)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn get_screen_view(connection: &xcb::Connection, screen: u32) -> Result<Geometry> {
+        let reply = xcb::get_geometry(connection, screen).get_reply()?;
+        Ok(Geometry::new(0, 0, u32::from(reply.width()), u32::from(reply.height())))
+    }
+
+    fn create_tab_bar(&self) -> Result<TabBar> {
+        let screen = self.connection.get_setup().roots().next().ok_or(Error::NoScreen)?;
+        let window = self.connection.generate_id();
+        let values = [
+            (xcb::CW_BACK_PIXEL, self.tab_unfocused_color),
+            (xcb::CW_OVERRIDE_REDIRECT, 1),
+            (xcb::CW_EVENT_MASK, xcb::EVENT_MASK_EXPOSURE | xcb::EVENT_MASK_BUTTON_PRESS),
+        ];
+        xcb::create_window(
+            &self.connection,
+            xcb::COPY_FROM_PARENT as u8,
+            window,
+            screen.root(),
+            0, 0, 1, 1, 0,
+            xcb::WINDOW_CLASS_INPUT_OUTPUT as u16,
+            screen.root_visual(),
+            &values,
+        );
+        let font = self.connection.generate_id();
+        xcb::open_font(&self.connection, font, "fixed");
+        let gc = self.connection.generate_id();
+        xcb::create_gc(&self.connection, gc, window, &[(xcb::GC_FONT, font)]);
+        xcb::close_font(&self.connection, font);
+        Ok(TabBar { window, gc, view: Geometry::default(), tabs: Vec::new() })
+    }
+
+    fn draw_tab_bar(&self, tab_bar: &TabBar) {
+        if tab_bar.tabs.is_empty() { return; }
+        let tab_width = tab_bar.view.size.width / tab_bar.tabs.len() as u32;
+        let height = tab_bar.view.size.height;
+        tab_bar.tabs.iter().enumerate()
+            .for_each(|(pos, (_, title, focused))| {
+                let color = if *focused { self.tab_focused_color } else { self.tab_unfocused_color };
+                let x = (tab_width * pos as u32) as i16;
+                let rectangle = xcb::Rectangle::new(x, 0, tab_width as u16, height as u16);
+                xcb::change_gc(&self.connection, tab_bar.gc, &[(xcb::GC_FOREGROUND, color)]);
+                xcb::poly_fill_rectangle(&self.connection, tab_bar.window, tab_bar.gc, &[rectangle]);
+                // The "fixed" font is 6 pixels wide, keep the title inside its tab
+                let title = truncate(title, (tab_width as usize / 6).saturating_sub(1));
+                let text_values = [(xcb::GC_FOREGROUND, self.tab_text_color), (xcb::GC_BACKGROUND, color)];
+                xcb::change_gc(&self.connection, tab_bar.gc, &text_values);
+                xcb::image_text_8(&self.connection, tab_bar.window, tab_bar.gc, x + 4, height as i16 - 5, title);
+            });
+    }
+
+    fn get_title(&self, window: xcb::Window) -> String {
+        ewmh::get_wm_name(&self.connection, window).get_reply()
+            .map(|reply| reply.string().to_string())
+            .ok()
+            .filter(|title| !title.is_empty())
+            .or_else(|| {
+                xcb::get_property(&self.connection, false, window, xcb::ATOM_WM_NAME, xcb::ATOM_STRING, 0, 1024)
+                    .get_reply()
+                    .map(|reply| String::from_utf8_lossy(reply.value::<u8>()).to_string())
+                    .ok()
+            })
+            .unwrap_or_default()
+    }
+
+    fn get_properties(&self, window: xcb::Window) -> WindowProperties {
+        let (class, instance) = icccm::get_wm_class(&self.connection, window).get_reply()
+            .map(|reply| (reply.class().to_string(), reply.instance().to_string()))
+            .unwrap_or_default();
+        let role = xcb::get_property(&self.connection, false, window, self.window_role, xcb::ATOM_STRING, 0, 1024)
             .get_reply()
-            .unwrap();
-        Geometry::new(0, 0, u32::from(reply.width()), u32::from(reply.height()))
+            .map(|reply| String::from_utf8_lossy(reply.value::<u8>()).to_string())
+            .unwrap_or_default();
+        WindowProperties { class, instance, title: self.get_title(window), role }
+    }
+
+    /// Queues the commands appended to the root window property and deletes
+    /// it, so the next ones start from an empty property.
+    fn read_commands(&self, root: xcb::Window) {
+        let reply = xcb::get_property(&self.connection, true, root, self.command_property, xcb::ATOM_STRING, 0, u32::MAX / 4).get_reply();
+        if let Ok(reply) = reply {
+            let mut events = self.events.borrow_mut();
+            String::from_utf8_lossy(reply.value::<u8>()).lines()
+                .filter(|command| !command.trim().is_empty())
+                .for_each(|command| events.push(Event::CommandReceived(command.to_string())));
+        }
+    }
+
+    /// Follows the property changes of a window and queues its current properties.
+    fn watch_window(&self, window: xcb::Window) {
+        xcb::change_window_attributes(&self.connection, window, &[(xcb::CW_EVENT_MASK, CLIENT_EVENT_MASK)]);
+        self.events.borrow_mut().push(Event::WindowPropertyChanged(window, self.get_properties(window)));
+    }
+
+    /// Compares the monitors against the known ones and queues an event for
+    /// every screen which appeared, disappeared or changed.
+    fn update_screens(&self) -> Event<xcb::Window, XcbKeyCombo> {
+        let monitors = self.roots.iter()
+            .flat_map(|root| Self::get_screen_views(&self.connection, *root))
+            .collect();
+        let mut known_screens = self.screens.borrow_mut();
+        let screens = assign_screen_ids(&known_screens, monitors);
+        let mut events = self.events.borrow_mut();
+        known_screens.iter()
+            .filter(|(screen, _, _)| !screens.iter().any(|(other, _, _)| other == screen))
+            .for_each(|(screen, _, _)| events.push(Event::ScreenRemoved(*screen)));
+        screens.iter()
+            .for_each(|(screen, _, view)| {
+                match known_screens.iter().find(|(other, _, _)| other == screen) {
+                    Some((_, _, old_view)) if old_view == view => {},
+                    Some(_) => events.push(Event::ScreenChanged(*screen, view.clone())),
+                    None => events.push(Event::ScreenAdded(*screen, view.clone())),
+                }
+            });
+        *known_screens = screens;
+        Event::Ignored
     }
 
     fn match_event(&self, event: xcb::GenericEvent) -> Event<xcb::Window, XcbKeyCombo> {
+        if let Some(randr_event) = self.randr_event {
+            let response_type = event.response_type();
+            if response_type == randr_event + xcb::randr::SCREEN_CHANGE_NOTIFY || response_type == randr_event + xcb::randr::NOTIFY {
+                return self.update_screens();
+            }
+        }
         match event.response_type() {
             xcb::CONFIGURE_REQUEST => {
                 Event::Ignored
             }
+            xcb::EXPOSE => {
+                let expose: &xcb::ExposeEvent = unsafe { xcb::cast_event(&event) };
+                self.tab_bars.borrow().values()
+                    .filter(|tab_bar| tab_bar.window == expose.window())
+                    .for_each(|tab_bar| self.draw_tab_bar(tab_bar));
+                Event::Ignored
+            }
+            xcb::BUTTON_PRESS => {
+                let button_press: &xcb::ButtonPressEvent = unsafe { xcb::cast_event(&event) };
+                if self.roots.contains(&button_press.event()) && button_press.child() == xcb::NONE {
+                    return Event::RootButtonPressed(button_press.detail());
+                }
+                self.tab_bars.borrow().values()
+                    .find(|tab_bar| tab_bar.window == button_press.event() && !tab_bar.tabs.is_empty())
+                    .and_then(|tab_bar| {
+                        let tab_width = (tab_bar.view.size.width / tab_bar.tabs.len() as u32).max(1);
+                        tab_bar.tabs.get(button_press.event_x() as usize / tab_width as usize)
+                    })
+                    .map(|(window, _, _)| Event::WindowFocused(*window))
+                    .unwrap_or(Event::Ignored)
+            }
             xcb::KEY_PRESS => {
                 let key_press: &xcb::KeyPressEvent = unsafe { xcb::cast_event(&event) };
                 let key_symbols = KeySymbols::new(&self.connection);
@@ -131,8 +651,33 @@ impl XcbDisplayServer {
             }
             xcb::MAP_REQUEST => {
                 let map_request: &xcb::MapRequestEvent = unsafe { xcb::cast_event(&event) };
+                self.watch_window(map_request.window());
                 Event::WindowAdded(map_request.window(), WindowType::Normal)
             }
+            xcb::PROPERTY_NOTIFY => {
+                let property_notify: &xcb::PropertyNotifyEvent = unsafe { xcb::cast_event(&event) };
+                let window = property_notify.window();
+                if self.roots.contains(&window) {
+                    if property_notify.atom() == self.command_property && property_notify.state() == xcb::PROPERTY_NEW_VALUE as u8 {
+                        self.read_commands(window);
+                    }
+                    return Event::Ignored;
+                }
+                let watched = [xcb::ATOM_WM_NAME, xcb::ATOM_WM_CLASS, self.connection.WM_NAME(), self.window_role];
+                if !watched.contains(&property_notify.atom()) || self.selections.contains(&window) {
+                    return Event::Ignored;
+                }
+                // the tab bars draw the titles themselves
+                let properties = self.get_properties(window);
+                self.tab_bars.borrow_mut().values_mut()
+                    .for_each(|tab_bar| {
+                        let mut tabs = tab_bar.tabs.iter_mut().filter(|(tab, _, _)| *tab == window).peekable();
+                        if tabs.peek().is_none() { return; }
+                        tabs.for_each(|(_, title, _)| *title = properties.title.clone());
+                        self.draw_tab_bar(tab_bar);
+                    });
+                Event::WindowPropertyChanged(window, properties)
+            }
             xcb::UNMAP_NOTIFY => {
                 let unmap_notify: &xcb::UnmapNotifyEvent = unsafe { xcb::cast_event(&event) };
                 // if unmap_notify.event() == self.root {
@@ -145,6 +690,14 @@ impl XcbDisplayServer {
                 let destroy_event: &xcb::DestroyNotifyEvent = unsafe { xcb::cast_event(&event) };
                 Event::WindowRemoved(destroy_event.window())
             }
+            xcb::SELECTION_CLEAR => {
+                let selection_clear: &xcb::SelectionClearEvent = unsafe { xcb::cast_event(&event) };
+                if self.selections.contains(&selection_clear.owner()) {
+                    log::info!("Replaced by another window manager");
+                    self.quit();
+                }
+                Event::Ignored
+            }
             xcb::ENTER_NOTIFY => {
                 let enter_event: &xcb::EnterNotifyEvent = unsafe { xcb::cast_event(&event) };
                 Event::WindowFocused(enter_event.event())
@@ -153,3 +706,65 @@ impl XcbDisplayServer {
         }
     }
 }
+
+/// Gives each monitor the id of the known screen it was found from, or else
+/// of the known screen showing the same area, so switching from RandR to
+/// Xinerama keeps the screens. Other monitors get new ids.
+fn assign_screen_ids(known: &[(u32, Monitor, Geometry)], monitors: Vec<(Monitor, Geometry)>) -> Vec<(u32, Monitor, Geometry)> {
+    let mut next_id = known.iter().map(|(id, _, _)| id + 1).max().unwrap_or(1);
+    let mut screens: Vec<(u32, Monitor, Geometry)> = Vec::new();
+    monitors.into_iter()
+        .for_each(|(monitor, view)| {
+            let is_free = |id: &u32| !screens.iter().any(|(other, _, _)| other == id);
+            let id = known.iter().find(|(id, other, _)| *other == monitor && is_free(id))
+                .or_else(|| known.iter().find(|(id, _, other)| *other == view && is_free(id)))
+                .map(|(id, _, _)| *id)
+                .unwrap_or_else(|| {
+                    next_id += 1;
+                    next_id - 1
+                });
+            screens.push((id, monitor, view));
+        });
+    screens
+}
+
+/// Cuts a title to at most `chars` characters and the 255 bytes a single
+/// `ImageText8` request can carry, without splitting a character.
+fn truncate(title: &str, chars: usize) -> &str {
+    let mut end = title.char_indices().nth(chars).map_or(title.len(), |(index, _)| index).min(255);
+    while !title.is_char_boundary(end) { end -= 1; }
+    &title[..end]
+}
+
+#[cfg(test)]
+mod xcb_server_tests {
+    use crate::displays::xcb_server::{truncate, assign_screen_ids, Monitor};
+    use crate::window::Geometry;
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!("ter", truncate("terminal", 3));
+        assert_eq!("terminal", truncate("terminal", 20));
+        assert_eq!("é", truncate("éé", 1));
+        let title = "é".repeat(200);
+        assert_eq!(254, truncate(&title, 300).len());
+    }
+
+    #[test]
+    fn test_screen_ids() {
+        let left = Geometry::new(0, 0, 100, 100);
+        let right = Geometry::new(100, 0, 100, 100);
+        let screens = assign_screen_ids(&[], vec![(Monitor::Crtc(63), left.clone()), (Monitor::Crtc(64), right.clone())]);
+        assert_eq!(vec![(1, Monitor::Crtc(63), left.clone()), (2, Monitor::Crtc(64), right.clone())], screens);
+        // a resized monitor keeps its id
+        let wide = Geometry::new(100, 0, 200, 100);
+        let screens = assign_screen_ids(&screens, vec![(Monitor::Crtc(63), left.clone()), (Monitor::Crtc(64), wide.clone())]);
+        assert_eq!(vec![(1, Monitor::Crtc(63), left.clone()), (2, Monitor::Crtc(64), wide.clone())], screens);
+        // falling back to Xinerama finds the same areas
+        let screens = assign_screen_ids(&screens, vec![(Monitor::Xinerama(0), left.clone()), (Monitor::Xinerama(1), wide.clone())]);
+        assert_eq!(vec![(1, Monitor::Xinerama(0), left.clone()), (2, Monitor::Xinerama(1), wide.clone())], screens);
+        // other monitors get new ids
+        let screens = assign_screen_ids(&screens[..1], vec![(Monitor::Xinerama(0), left.clone()), (Monitor::Xinerama(1), right.clone())]);
+        assert_eq!(vec![(1, Monitor::Xinerama(0), left), (2, Monitor::Xinerama(1), right)], screens);
+    }
+}
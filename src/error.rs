@@ -0,0 +1,84 @@
+use std::{error, fmt, io};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The X server could not be reached.
+    Connection(xcb::ConnError),
+    /// An X request failed with the given error code.
+    Request(u8),
+    /// Another window manager already manages the screen.
+    OtherWindowManager,
+    /// The display has no screen to manage.
+    NoScreen,
+    InvalidModKey(String),
+    InvalidLayout(String),
+    InvalidModifier(String),
+    InvalidFocusPolicy(String),
+    /// The config file could not be read or has an invalid line.
+    InvalidConfig(String),
+    InvalidArgument(String),
+    /// A command sent with `rwm --command` is unknown or misses its argument.
+    InvalidCommand(String),
+    /// A line of a saved snapshot could not be read.
+    InvalidSnapshot(String),
+    Io(io::Error),
+    Logger(log::SetLoggerError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Connection(error) => write!(f, "cannot connect to the display: {}", error),
+            Error::Request(code) => write!(f, "X request failed with error code {}", code),
+            Error::OtherWindowManager => write!(f, "another window manager is already running"),
+            Error::NoScreen => write!(f, "the display has no screen"),
+            Error::InvalidModKey(key) => write!(f, "invalid modifier key {:?}", key),
+            Error::InvalidLayout(layout) => write!(f, "invalid layout {:?}", layout),
+            Error::InvalidModifier(modifier) => write!(f, "invalid modifier {:?}", modifier),
+            Error::InvalidFocusPolicy(policy) => write!(f, "invalid focus policy {:?}", policy),
+            Error::InvalidConfig(message) => write!(f, "invalid config {}", message),
+            Error::InvalidArgument(message) => write!(f, "{}", message),
+            Error::InvalidCommand(command) => write!(f, "invalid command {:?}", command),
+            Error::InvalidSnapshot(line) => write!(f, "invalid snapshot line {:?}", line),
+            Error::Io(error) => write!(f, "{}", error),
+            Error::Logger(error) => write!(f, "cannot set up logging: {}", error),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Connection(error) => Some(error),
+            Error::Io(error) => Some(error),
+            Error::Logger(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<xcb::ConnError> for Error {
+    fn from(error: xcb::ConnError) -> Self {
+        Error::Connection(error)
+    }
+}
+
+impl From<xcb::GenericError> for Error {
+    fn from(error: xcb::GenericError) -> Self {
+        Error::Request(error.error_code())
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<log::SetLoggerError> for Error {
+    fn from(error: log::SetLoggerError) -> Self {
+        Error::Logger(error)
+    }
+}
@@ -1,6 +1,9 @@
+use crate::error::Error;
+use std::convert::TryFrom;
+
 pub mod xcb_keys;
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum ModKey {
     Shift,
     Lock,
@@ -12,27 +15,29 @@ pub enum ModKey {
     Mod5,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct Key(pub char);
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct KeyCombo {
     pub mod_keys: Vec<ModKey>,
     pub key: Key
 }
 
-impl From<String> for ModKey {
-    fn from(key: String) -> Self {
-        match key.as_str() {
-            "shift" => ModKey::Shift,
-            "ctrl" => ModKey::Control,
-            "lock" => ModKey::Lock,
-            "mod1" => ModKey::Mod1,
-            "mod2" => ModKey::Mod2,
-            "mod3" => ModKey::Mod3,
-            "mod4" => ModKey::Mod4,
-            "mod5" => ModKey::Mod5,
-            _ => panic!("Invalid modifier key")
+impl TryFrom<&str> for ModKey {
+    type Error = Error;
+
+    fn try_from(key: &str) -> Result<Self, Self::Error> {
+        match key {
+            "shift" => Ok(ModKey::Shift),
+            "ctrl" => Ok(ModKey::Control),
+            "lock" => Ok(ModKey::Lock),
+            "mod1" => Ok(ModKey::Mod1),
+            "mod2" => Ok(ModKey::Mod2),
+            "mod3" => Ok(ModKey::Mod3),
+            "mod4" => Ok(ModKey::Mod4),
+            "mod5" => Ok(ModKey::Mod5),
+            _ => Err(Error::InvalidModKey(key.to_string())),
         }
     }
 }
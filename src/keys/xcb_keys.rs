@@ -23,7 +23,14 @@ impl From<ModKey> for u32 {
 
 impl From<Key> for u32 {
     fn from(key: Key) -> Self {
-        key.0 as u32
+        // Latin-1 keysyms match their character, control keys live in the 0xff00 page
+        match key.0 {
+            '\u{8}' => 0xff08,
+            '\t' => 0xff09,
+            '\n' | '\r' => 0xff0d,
+            '\u{1b}' => 0xff1b,
+            key => key as u32,
+        }
     }
 }
 
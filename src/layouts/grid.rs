@@ -0,0 +1,62 @@
+use crate::window::{Window, Geometry};
+use crate::stack::Stack;
+
+pub fn handle_layout<W>(view: &Geometry, windows: Stack<Window<W>>) -> Stack<Window<W>> {
+    let window_count = windows.len() as u32;
+    if window_count == 0 { return windows; };
+    let cols = (1..).find(|cols| cols * cols >= window_count).unwrap();
+    let rows = (window_count + cols - 1) / cols;
+    let height = view.size.height / rows;
+    windows.into_iter().enumerate()
+        .map(|(pos, (is_current, window))| {
+            let pos = pos as u32;
+            let row = pos / cols;
+            let col = pos % cols;
+            let row_cols = if row == rows - 1 { window_count - row * cols } else { cols };
+            let width = view.size.width / row_cols;
+            let x = (col * width) as i32 + view.position.x;
+            let y = (row * height) as i32 + view.position.y;
+            let height = if row == rows - 1 { view.size.height - row * height } else { height };
+            (is_current, window.set_view(Geometry::new(x, y, width, height)).visible(true))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod grid_tests {
+    use crate::layouts::grid;
+    use crate::window::{Window, Geometry, WindowType};
+    use crate::stack::Stack;
+
+    fn views(count: u32) -> Vec<Geometry> {
+        let windows: Stack<Window<u32>> = (0..count).map(|id| (id == 0, Window::new(id, WindowType::Normal))).collect();
+        grid::handle_layout(&Geometry::new(0, 0, 120, 100), windows).iter()
+            .filter_map(|window| window.get_view().cloned())
+            .collect()
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(vec![Geometry::new(0, 0, 120, 100)], views(1));
+        assert_eq!(vec![Geometry::new(0, 0, 60, 100), Geometry::new(60, 0, 60, 100)], views(2));
+        // the last row shares its width between the windows left
+        assert_eq!(vec![
+            Geometry::new(0, 0, 60, 50),
+            Geometry::new(60, 0, 60, 50),
+            Geometry::new(0, 50, 120, 50),
+        ], views(3));
+        assert_eq!(vec![
+            Geometry::new(0, 0, 40, 50),
+            Geometry::new(40, 0, 40, 50),
+            Geometry::new(80, 0, 40, 50),
+            Geometry::new(0, 50, 60, 50),
+            Geometry::new(60, 50, 60, 50),
+        ], views(5));
+        // the last row takes the height left over by the division
+        assert_eq!(Geometry::new(0, 66, 120, 34), views(7)[6]);
+        for count in 1..=10 {
+            let bottom = views(count).iter().map(|view| view.position.y as u32 + view.size.height).max();
+            assert_eq!(Some(100), bottom);
+        }
+    }
+}
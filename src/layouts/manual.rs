@@ -0,0 +1,388 @@
+use crate::window::{Window, Geometry};
+use crate::stack::Stack;
+use crate::error::Error;
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+use std::ops::Deref;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Horizontal,
+    Vertical,
+}
+
+type Children<W> = Vec<(f32, Node<W>)>;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node<W> {
+    Leaf(W),
+    Split(Direction, Children<W>),
+}
+
+/// Tree of split containers used by the manual layout, each child of a split
+/// takes a share of its container proportional to its weight.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SplitTree<W> {
+    root: Option<Node<W>>,
+    preselect: Direction,
+}
+
+impl Direction {
+    fn toggle(self) -> Self {
+        match self {
+            Direction::Horizontal => Direction::Vertical,
+            Direction::Vertical => Direction::Horizontal,
+        }
+    }
+}
+
+impl<W> Default for SplitTree<W> {
+    fn default() -> Self {
+        Self { root: None, preselect: Direction::Horizontal }
+    }
+}
+
+impl<W: Clone + Eq> Node<W> {
+    fn path_to(&self, window: &W) -> Option<Vec<usize>> {
+        match self {
+            Node::Leaf(leaf) => if leaf == window { Some(Vec::new()) } else { None },
+            Node::Split(_, children) => children.iter().enumerate()
+                .find_map(|(index, (_, child))| {
+                    let mut path = child.path_to(window)?;
+                    path.insert(0, index);
+                    Some(path)
+                }),
+        }
+    }
+
+    fn node_mut(&mut self, path: &[usize]) -> &mut Node<W> {
+        match (path.split_first(), self) {
+            (Some((index, rest)), Node::Split(_, children)) => children[*index].1.node_mut(rest),
+            (_, node) => node,
+        }
+    }
+
+    fn children_mut(&mut self) -> Option<(&mut Direction, &mut Children<W>)> {
+        match self {
+            Node::Split(direction, children) => Some((direction, children)),
+            Node::Leaf(_) => None,
+        }
+    }
+
+    fn filter_map<V, F: Fn(&W) -> Option<V>>(&self, f: &F) -> Option<Node<V>> {
+        match self {
+            Node::Leaf(window) => f(window).map(Node::Leaf),
+            Node::Split(direction, children) => Some(Node::Split(*direction, children.iter()
+                .filter_map(|(weight, child)| child.filter_map(f).map(|child| (*weight, child)))
+                .collect())),
+        }
+    }
+
+    /// Drops empty splits and replaces splits holding a single child by that child.
+    fn normalize(self) -> Option<Self> {
+        match self {
+            Node::Leaf(_) => Some(self),
+            Node::Split(direction, children) => {
+                let mut children = children.into_iter()
+                    .filter_map(|(weight, child)| child.normalize().map(|child| (weight, child)))
+                    .collect::<Vec<_>>();
+                match children.len() {
+                    0 => None,
+                    1 => children.pop().map(|(_, child)| child),
+                    _ => Some(Node::Split(direction, children)),
+                }
+            }
+        }
+    }
+
+    fn views(&self, view: &Geometry, views: &mut Vec<(W, Geometry)>) {
+        match self {
+            Node::Leaf(window) => views.push((window.clone(), view.clone())),
+            Node::Split(direction, children) => {
+                let total = children.iter().map(|(weight, _)| weight).sum::<f32>();
+                let length = match direction {
+                    Direction::Horizontal => view.size.width,
+                    Direction::Vertical => view.size.height,
+                };
+                let mut offset = 0;
+                children.iter().enumerate().for_each(|(index, (weight, child))| {
+                    let size = if index == children.len() - 1 {
+                        length.saturating_sub(offset)
+                    } else {
+                        (length as f32 * weight / total) as u32
+                    };
+                    let child_view = match direction {
+                        Direction::Horizontal => Geometry::new(view.position.x + offset as i32, view.position.y, size, view.size.height),
+                        Direction::Vertical => Geometry::new(view.position.x, view.position.y + offset as i32, view.size.width, size),
+                    };
+                    child.views(&child_view, views);
+                    offset += size;
+                });
+            }
+        }
+    }
+}
+
+impl<W: Clone + Eq> SplitTree<W> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts `window` next to `focused`, splitting in the preselected direction.
+    pub fn insert(mut self, focused: Option<&W>, window: W) -> Self {
+        let direction = self.preselect;
+        let path = focused.and_then(|focused| self.root.as_ref()?.path_to(focused));
+        self.root = match (self.root, path) {
+            (None, _) => Some(Node::Leaf(window)),
+            (Some(mut root), Some(path)) if !path.is_empty() => {
+                let (index, parent_path) = path.split_last().unwrap();
+                let (parent_direction, children) = root.node_mut(parent_path).children_mut().unwrap();
+                if *parent_direction == direction {
+                    let weight = children[*index].0;
+                    children.insert(index + 1, (weight, Node::Leaf(window)));
+                } else {
+                    let (weight, leaf) = children.remove(*index);
+                    children.insert(*index, (weight, Node::Split(direction, vec![(1.0, leaf), (1.0, Node::Leaf(window))])));
+                }
+                Some(root)
+            },
+            (Some(Node::Split(root_direction, mut children)), _) if root_direction == direction => {
+                children.push((1.0, Node::Leaf(window)));
+                Some(Node::Split(root_direction, children))
+            },
+            (Some(root), _) => Some(Node::Split(direction, vec![(1.0, root), (1.0, Node::Leaf(window))])),
+        };
+        self
+    }
+
+    pub fn remove(mut self, window: &W) -> Self {
+        let path = self.root.as_ref().and_then(|root| root.path_to(window));
+        self.root = match (self.root, path) {
+            (Some(mut root), Some(path)) if !path.is_empty() => {
+                let (index, parent_path) = path.split_last().unwrap();
+                root.node_mut(parent_path).children_mut().unwrap().1.remove(*index);
+                root.normalize()
+            },
+            (_, Some(_)) => None,
+            (root, None) => root,
+        };
+        self
+    }
+
+    pub fn contains(&self, window: &W) -> bool {
+        self.root.as_ref().is_some_and(|root| root.path_to(window).is_some())
+    }
+
+    /// The same splits over other windows, leaves mapped to `None` are dropped.
+    pub fn filter_map<V: Clone + Eq, F: Fn(&W) -> Option<V>>(&self, f: F) -> SplitTree<V> {
+        let root = self.root.as_ref().and_then(|root| root.filter_map(&f)).and_then(Node::normalize);
+        SplitTree { root, preselect: self.preselect }
+    }
+
+    pub fn preselect(mut self, direction: Direction) -> Self {
+        self.preselect = direction;
+        self
+    }
+
+    pub fn toggle_direction(mut self, focused: &W) -> Self {
+        if let Some((direction, _)) = self.container_of(focused) {
+            *direction = direction.toggle();
+        }
+        self
+    }
+
+    pub fn resize(mut self, focused: &W, delta: f32) -> Self {
+        let index = self.root.as_ref()
+            .and_then(|root| root.path_to(focused))
+            .and_then(|path| path.last().cloned());
+        if let (Some(index), Some((_, children))) = (index, self.container_of(focused)) {
+            let weight = &mut children[index].0;
+            *weight = (*weight + delta).max(0.1);
+        }
+        self
+    }
+
+    /// Moves `focused` into the neighbouring split, or out of its own split
+    /// when it is already at the edge of it.
+    pub fn move_window(mut self, focused: &W, forward: bool) -> Self {
+        let path = match self.root.as_ref().and_then(|root| root.path_to(focused)) {
+            Some(path) if !path.is_empty() => path,
+            _ => return self,
+        };
+        let mut root = self.root.take().unwrap();
+        let (index, parent_path) = path.split_last().unwrap();
+        let (_, children) = root.node_mut(parent_path).children_mut().unwrap();
+        let target = if forward { Some(index + 1) } else { index.checked_sub(1) }
+            .filter(|target| *target < children.len());
+        match target {
+            Some(target) if children[target].1.children_mut().is_some() => {
+                let leaf = children.remove(*index);
+                let target = if forward { target - 1 } else { target };
+                let (_, target_children) = children[target].1.children_mut().unwrap();
+                let position = if forward { 0 } else { target_children.len() };
+                target_children.insert(position, leaf);
+            },
+            Some(target) => children.swap(*index, target),
+            None if !parent_path.is_empty() => {
+                let leaf = children.remove(*index);
+                let (parent_index, grand_parent_path) = parent_path.split_last().unwrap();
+                let (_, grand_children) = root.node_mut(grand_parent_path).children_mut().unwrap();
+                let position = if forward { parent_index + 1 } else { *parent_index };
+                grand_children.insert(position, leaf);
+            },
+            None => {},
+        }
+        self.root = root.normalize();
+        self
+    }
+
+    fn container_of(&mut self, window: &W) -> Option<(&mut Direction, &mut Children<W>)> {
+        let path = self.root.as_ref()?.path_to(window)?;
+        let (_, parent_path) = path.split_last()?;
+        self.root.as_mut()?.node_mut(parent_path).children_mut()
+    }
+
+    fn views(&self, view: &Geometry) -> Vec<(W, Geometry)> {
+        let mut views = Vec::new();
+        if let Some(root) = &self.root {
+            root.views(view, &mut views);
+        }
+        views
+    }
+}
+
+impl<W: Display> Display for Node<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Node::Leaf(window) => write!(f, "{}", window),
+            Node::Split(direction, children) => {
+                let direction = if *direction == Direction::Horizontal { "h" } else { "v" };
+                let children = children.iter().map(|(weight, child)| format!("{}:{}", weight, child)).collect::<Vec<_>>();
+                write!(f, "{}({})", direction, children.join(","))
+            },
+        }
+    }
+}
+
+/// Leaves are the windows, splits are `h(` or `v(` followed by the comma
+/// separated `weight:child` pairs and `)`, as in `h(1:3,1.5:v(1:4,1:5))`.
+/// An empty tree is `-`.
+impl<W: Display> Display for SplitTree<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.root {
+            Some(root) => write!(f, "{}", root),
+            None => write!(f, "-"),
+        }
+    }
+}
+
+/// Reads a node from the start of `text`, returning what follows it.
+fn parse_node<W: FromStr>(text: &str) -> Option<(Node<W>, &str)> {
+    let direction = match text.get(..2) {
+        Some("h(") => Some(Direction::Horizontal),
+        Some("v(") => Some(Direction::Vertical),
+        _ => None,
+    };
+    let direction = match direction {
+        Some(direction) => direction,
+        None => {
+            let end = text.find(&[',', ')'][..]).unwrap_or(text.len());
+            return Some((Node::Leaf(text[..end].parse().ok()?), &text[end..]));
+        },
+    };
+    let mut rest = &text[2..];
+    let mut children = Vec::new();
+    loop {
+        let (weight, child) = rest.split_once(':')?;
+        let (child, after) = parse_node(child)?;
+        let weight: f32 = weight.parse().ok().filter(|weight: &f32| weight.is_finite() && *weight > 0.0)?;
+        children.push((weight, child));
+        match after.chars().next()? {
+            ',' => rest = &after[1..],
+            ')' => return Some((Node::Split(direction, children), &after[1..])),
+            _ => return None,
+        }
+    }
+}
+
+impl<W: FromStr> TryFrom<&str> for SplitTree<W> {
+    type Error = Error;
+
+    fn try_from(text: &str) -> Result<Self, Self::Error> {
+        let root = match text {
+            "-" => None,
+            text => match parse_node(text) {
+                Some((root, "")) => Some(root),
+                _ => return Err(Error::InvalidSnapshot(text.to_string())),
+            },
+        };
+        Ok(SplitTree { root, preselect: Direction::Horizontal })
+    }
+}
+
+pub fn handle_layout<W: Clone + Eq>(view: &Geometry, windows: Stack<Window<W>>, tree: &SplitTree<W>) -> Stack<Window<W>> {
+    let views = tree.views(view);
+    windows.into_iter()
+        .map(|(is_current, window)| {
+            match views.iter().find(|(id, _)| id == window.deref()) {
+                Some((_, window_view)) => (is_current, window.set_view(window_view.clone()).visible(true)),
+                None => (is_current, window.set_view(view.clone()).visible(false)),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod manual_tests {
+    use crate::layouts::manual::{SplitTree, Direction};
+    use crate::window::Geometry;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn test() {
+        let tree = SplitTree::new()
+            .insert(None, 1)
+            .insert(Some(&1), 2)
+            .preselect(Direction::Vertical)
+            .insert(Some(&2), 3);
+        let expected = vec![
+            (1, Geometry::new(0, 0, 50, 100)),
+            (2, Geometry::new(50, 0, 50, 50)),
+            (3, Geometry::new(50, 50, 50, 50)),
+        ];
+        assert_eq!(expected, tree.views(&Geometry::new(0, 0, 100, 100)));
+
+        let tree = tree.move_window(&3, false).remove(&2);
+        let expected = vec![
+            (1, Geometry::new(0, 0, 50, 100)),
+            (3, Geometry::new(50, 0, 50, 100)),
+        ];
+        assert_eq!(expected, tree.views(&Geometry::new(0, 0, 100, 100)));
+    }
+
+    #[test]
+    fn test_text() {
+        let tree = SplitTree::new()
+            .insert(None, 1)
+            .preselect(Direction::Vertical)
+            .insert(Some(&1), 2)
+            .insert(Some(&2), 3)
+            .resize(&3, 0.5)
+            .preselect(Direction::Horizontal);
+        assert_eq!("v(1:1,1:2,1.5:3)", tree.to_string());
+        assert_eq!(tree, SplitTree::try_from("v(1:1,1:2,1.5:3)").unwrap());
+        assert_eq!("v(1:10,1.5:30)", tree.filter_map(|w| if *w == 2 { None } else { Some(w * 10) }).to_string());
+        assert_eq!("-", tree.filter_map(|_| None::<u32>).to_string());
+
+        let tree = SplitTree::<u32>::try_from("h(1:4,2:v(1:5,1:6))").unwrap();
+        assert_eq!(Geometry::new(30, 0, 60, 45), tree.views(&Geometry::new(0, 0, 90, 90))[1].1);
+        assert!(SplitTree::<u32>::try_from("h(1:4,2:v(1:5)").is_err());
+        assert!(SplitTree::<u32>::try_from("h(1:4").is_err());
+        // the weights must be positive numbers
+        assert!(SplitTree::<u32>::try_from("h(1:4,0:5)").is_err());
+        assert!(SplitTree::<u32>::try_from("h(1:4,-1:5)").is_err());
+        assert!(SplitTree::<u32>::try_from("h(1:4,NaN:5)").is_err());
+        assert!(SplitTree::<u32>::try_from("h(inf:4,1:5)").is_err());
+    }
+}
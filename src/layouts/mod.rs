@@ -1,31 +1,136 @@
 use crate::window::{Window, Geometry};
 use crate::stack::Stack;
-use std::fmt::Debug;
+use std::fmt::{self, Debug, Display};
+use std::mem;
+use crate::error::Error;
+use std::convert::TryFrom;
 
 mod fullscreen;
 mod tall;
+mod grid;
+mod spiral;
+mod three_column;
+pub mod tabbed;
+pub mod manual;
+pub mod modifiers;
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum Layout {
     Tall,
     FullScreen,
+    Grid,
+    Spiral { ratio: f32 },
+    Dwindle { ratio: f32 },
+    ThreeColumn { master_count: usize, ratio: f32 },
+    ThreeColumnLeft { master_count: usize, ratio: f32 },
+    Tabbed,
+    Manual,
 }
 
 impl Layout {
+    /// The manual layout needs the split tree of its workspace and is laid out
+    /// by `manual::handle_layout`, without it the windows are stacked full screen.
     pub fn handle_layout<W>(&self, view: &Geometry, windows: Stack<Window<W>>) -> Stack<Window<W>> {
         match self {
             Layout::Tall => tall::handle_layout(view, windows),
             Layout::FullScreen => fullscreen::handle_layout(view, windows),
+            Layout::Grid => grid::handle_layout(view, windows),
+            Layout::Spiral { ratio } => spiral::handle_layout(view, windows, *ratio),
+            Layout::Dwindle { ratio } => spiral::handle_dwindle_layout(view, windows, *ratio),
+            Layout::ThreeColumn { master_count, ratio } => three_column::handle_layout(view, windows, *master_count, *ratio, true),
+            Layout::ThreeColumnLeft { master_count, ratio } => three_column::handle_layout(view, windows, *master_count, *ratio, false),
+            Layout::Tabbed => tabbed::handle_layout(view, windows),
+            Layout::Manual => fullscreen::handle_layout(view, windows),
+        }
+    }
+
+    /// Grows or shrinks the share of the area the first windows take, for the
+    /// layouts which have one.
+    pub fn change_ratio(self, delta: f32) -> Self {
+        let resize = |ratio: f32| (ratio + delta).clamp(0.1, 0.9);
+        match self {
+            Layout::Spiral { ratio } => Layout::Spiral { ratio: resize(ratio) },
+            Layout::Dwindle { ratio } => Layout::Dwindle { ratio: resize(ratio) },
+            Layout::ThreeColumn { master_count, ratio } => Layout::ThreeColumn { master_count, ratio: resize(ratio) },
+            Layout::ThreeColumnLeft { master_count, ratio } => Layout::ThreeColumnLeft { master_count, ratio: resize(ratio) },
+            layout => layout,
+        }
+    }
+
+    /// Changes how many windows share the master column, keeping at least one.
+    pub fn change_master_count(self, delta: isize) -> Self {
+        let count = |master_count: usize| (master_count as isize + delta).max(1) as usize;
+        match self {
+            Layout::ThreeColumn { master_count, ratio } => Layout::ThreeColumn { master_count: count(master_count), ratio },
+            Layout::ThreeColumnLeft { master_count, ratio } => Layout::ThreeColumnLeft { master_count: count(master_count), ratio },
+            layout => layout,
+        }
+    }
+
+    /// Same layout, whatever its parameters.
+    pub fn is_same_kind(&self, other: &Layout) -> bool {
+        mem::discriminant(self) == mem::discriminant(other)
+    }
+
+    pub fn tab_bar(&self, view: &Geometry) -> Option<Geometry> {
+        match self {
+            Layout::Tabbed => Some(tabbed::tab_bar_view(view)),
+            _ => None,
         }
     }
 }
 
-impl From<&str> for Layout {
-    fn from(display: &str) -> Self {
-        match display {
-            "tall" => Layout::Tall,
-            "fullscreen" => Layout::FullScreen,
-            _ => panic!("Invalid layout {}", display)
+/// The layout as read by `TryFrom<&str>`, with its parameters.
+impl Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Layout::Tall => write!(f, "tall"),
+            Layout::FullScreen => write!(f, "fullscreen"),
+            Layout::Grid => write!(f, "grid"),
+            Layout::Spiral { ratio } => write!(f, "spiral:{}", ratio),
+            Layout::Dwindle { ratio } => write!(f, "dwindle:{}", ratio),
+            Layout::ThreeColumn { master_count, ratio } => write!(f, "three_column:{}:{}", master_count, ratio),
+            Layout::ThreeColumnLeft { master_count, ratio } => write!(f, "three_column_left:{}:{}", master_count, ratio),
+            Layout::Tabbed => write!(f, "tabbed"),
+            Layout::Manual => write!(f, "manual"),
         }
     }
 }
+
+/// Parses the layout names, `spiral` and `dwindle` take an optional ratio
+/// such as `spiral:0.6`, the three column layouts an optional master count
+/// and ratio such as `three_column:2:0.6`.
+impl TryFrom<&str> for Layout {
+    type Error = Error;
+
+    fn try_from(display: &str) -> Result<Self, Self::Error> {
+        let ratio = |ratio: &str| ratio.parse().ok().filter(|ratio| *ratio > 0.0 && *ratio < 1.0);
+        let three_column = |value: &str| {
+            let mut values = value.splitn(2, ':');
+            let master_count = values.next()?.parse().ok().filter(|count| *count > 0)?;
+            let ratio = match values.next() {
+                Some(value) => ratio(value)?,
+                None => 0.5,
+            };
+            Some((master_count, ratio))
+        };
+        let mut parts = display.splitn(2, ':');
+        let layout = match (parts.next(), parts.next()) {
+            (Some("tall"), None) => Some(Layout::Tall),
+            (Some("fullscreen"), None) => Some(Layout::FullScreen),
+            (Some("grid"), None) => Some(Layout::Grid),
+            (Some("spiral"), None) => Some(Layout::Spiral { ratio: 0.5 }),
+            (Some("spiral"), Some(value)) => ratio(value).map(|ratio| Layout::Spiral { ratio }),
+            (Some("dwindle"), None) => Some(Layout::Dwindle { ratio: 0.5 }),
+            (Some("dwindle"), Some(value)) => ratio(value).map(|ratio| Layout::Dwindle { ratio }),
+            (Some("three_column"), None) => Some(Layout::ThreeColumn { master_count: 1, ratio: 0.5 }),
+            (Some("three_column"), Some(value)) => three_column(value).map(|(master_count, ratio)| Layout::ThreeColumn { master_count, ratio }),
+            (Some("three_column_left"), None) => Some(Layout::ThreeColumnLeft { master_count: 1, ratio: 0.5 }),
+            (Some("three_column_left"), Some(value)) => three_column(value).map(|(master_count, ratio)| Layout::ThreeColumnLeft { master_count, ratio }),
+            (Some("tabbed"), None) => Some(Layout::Tabbed),
+            (Some("manual"), None) => Some(Layout::Manual),
+            _ => None,
+        };
+        layout.ok_or_else(|| Error::InvalidLayout(display.to_string()))
+    }
+}
@@ -0,0 +1,197 @@
+use crate::window::{Window, Geometry};
+use crate::stack::Stack;
+use crate::error::Error;
+use std::convert::TryFrom;
+use std::mem;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Modifier {
+    ReflectHorizontal,
+    ReflectVertical,
+    Magnify(f32),
+    LimitWindows(usize),
+}
+
+/// Parses `reflect_horizontal`, `reflect_vertical`, `magnify:<factor>` and
+/// `limit_windows:<count>`.
+impl TryFrom<&str> for Modifier {
+    type Error = Error;
+
+    fn try_from(display: &str) -> Result<Self, Self::Error> {
+        let mut parts = display.splitn(2, ':');
+        let modifier = match (parts.next(), parts.next()) {
+            (Some("reflect_horizontal"), None) => Some(Modifier::ReflectHorizontal),
+            (Some("reflect_vertical"), None) => Some(Modifier::ReflectVertical),
+            (Some("magnify"), Some(factor)) => factor.parse().ok()
+                .filter(|factor: &f32| factor.is_finite() && *factor >= 1.0)
+                .map(Modifier::Magnify),
+            (Some("limit_windows"), Some(count)) => count.parse().ok()
+                .filter(|count| *count >= 1)
+                .map(Modifier::LimitWindows),
+            _ => None,
+        };
+        modifier.ok_or_else(|| Error::InvalidModifier(display.to_string()))
+    }
+}
+
+impl Modifier {
+    pub fn is_same_kind(&self, other: &Modifier) -> bool {
+        mem::discriminant(self) == mem::discriminant(other)
+    }
+
+    fn modify<W>(&self, view: &Geometry, is_current: bool, window: Window<W>) -> Window<W> {
+        let Geometry { position, size } = match window.get_view() {
+            Some(view) if window.is_visible() => view.clone(),
+            _ => return window,
+        };
+        match self {
+            Modifier::ReflectHorizontal => {
+                let x = 2 * view.position.x + view.size.width as i32 - position.x - size.width as i32;
+                window.set_view(Geometry::new(x, position.y, size.width, size.height))
+            },
+            Modifier::ReflectVertical => {
+                let y = 2 * view.position.y + view.size.height as i32 - position.y - size.height as i32;
+                window.set_view(Geometry::new(position.x, y, size.width, size.height))
+            },
+            Modifier::Magnify(factor) if is_current => {
+                let width = ((size.width as f32 * factor) as u32).min(view.size.width);
+                let height = ((size.height as f32 * factor) as u32).min(view.size.height);
+                let x = (position.x - (width as i32 - size.width as i32) / 2)
+                    .max(view.position.x)
+                    .min(view.position.x + (view.size.width - width) as i32);
+                let y = (position.y - (height as i32 - size.height as i32) / 2)
+                    .max(view.position.y)
+                    .min(view.position.y + (view.size.height - height) as i32);
+                window.set_view(Geometry::new(x, y, width, height))
+            },
+            _ => window,
+        }
+    }
+}
+
+/// Runs `layout` then applies every modifier to its output, windows beyond a
+/// window limit are left out of the layout and hidden.
+pub fn handle_layout<W, F>(layout: F, modifiers: &[Modifier], view: &Geometry, windows: Stack<Window<W>>) -> Stack<Window<W>>
+    where F: Fn(&Geometry, Stack<Window<W>>) -> Stack<Window<W>> {
+    let limit = modifiers.iter()
+        .filter_map(|modifier| match modifier {
+            Modifier::LimitWindows(limit) => Some((*limit).max(1)),
+            _ => None,
+        })
+        .min();
+    let windows = match limit {
+        Some(limit) if windows.len() > limit => limit_windows(layout, limit, view, windows),
+        _ => layout(view, windows),
+    };
+    windows.into_iter()
+        .map(|(is_current, window)| {
+            let window = modifiers.iter()
+                .fold(window, |window, modifier| modifier.modify(view, is_current, window));
+            (is_current, window)
+        })
+        .collect()
+}
+
+fn limit_windows<W, F>(layout: F, limit: usize, view: &Geometry, windows: Stack<Window<W>>) -> Stack<Window<W>>
+    where F: Fn(&Geometry, Stack<Window<W>>) -> Stack<Window<W>> {
+    let windows = windows.into_iter().collect::<Vec<_>>();
+    let current = windows.iter().position(|(is_current, _)| *is_current).unwrap_or(0);
+    let last = if current < limit { limit - 1 } else { current };
+    let mut shown = Vec::new();
+    let hidden = windows.into_iter().enumerate()
+        .map(|(pos, item)| {
+            if pos < limit - 1 || pos == last {
+                shown.push(item);
+                None
+            } else {
+                Some(item)
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut shown = layout(view, shown.into_iter().collect()).into_iter();
+    hidden.into_iter()
+        .map(|item| match item {
+            Some((is_current, window)) => (is_current, window.set_view(view.clone()).visible(false)),
+            None => shown.next().unwrap(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod modifiers_tests {
+    use crate::layouts::modifiers::{self, Modifier};
+    use crate::layouts::Layout;
+    use crate::window::{Window, Geometry, WindowType};
+    use crate::stack::Stack;
+    use std::convert::TryFrom;
+
+    /// Lays out `count` windows with the tall layout, `None` for the hidden ones.
+    fn views(modifiers: &[Modifier], count: u32, current: u32) -> Vec<Option<Geometry>> {
+        let windows: Stack<Window<u32>> = (0..count).map(|id| (id == current, Window::new(id, WindowType::Normal))).collect();
+        let tall = |view: &Geometry, windows| Layout::Tall.handle_layout(view, windows);
+        modifiers::handle_layout(tall, modifiers, &Geometry::new(0, 0, 100, 100), windows).iter()
+            .map(|window| window.get_view().filter(|_| window.is_visible()).cloned())
+            .collect()
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(vec![
+            Some(Geometry::new(50, 0, 50, 100)),
+            Some(Geometry::new(0, 0, 50, 50)),
+            Some(Geometry::new(0, 50, 50, 50)),
+        ], views(&[Modifier::ReflectHorizontal], 3, 0));
+        assert_eq!(vec![
+            Some(Geometry::new(0, 0, 50, 100)),
+            Some(Geometry::new(50, 50, 50, 50)),
+            Some(Geometry::new(50, 0, 50, 50)),
+        ], views(&[Modifier::ReflectVertical], 3, 0));
+        // the magnified window is kept inside the view
+        assert_eq!(vec![
+            Some(Geometry::new(0, 0, 50, 100)),
+            Some(Geometry::new(25, 0, 75, 75)),
+            Some(Geometry::new(50, 50, 50, 50)),
+        ], views(&[Modifier::Magnify(1.5)], 3, 1));
+        assert_eq!(vec![Some(Geometry::new(0, 0, 100, 100))], views(&[Modifier::Magnify(1.5)], 1, 0));
+    }
+
+    #[test]
+    fn test_limit_windows() {
+        assert_eq!(vec![
+            Some(Geometry::new(0, 0, 50, 100)),
+            Some(Geometry::new(50, 0, 50, 100)),
+        ], views(&[Modifier::LimitWindows(2)], 2, 0));
+        assert_eq!(vec![
+            Some(Geometry::new(0, 0, 50, 100)),
+            Some(Geometry::new(50, 0, 50, 100)),
+            None,
+            None,
+            None,
+        ], views(&[Modifier::LimitWindows(2)], 5, 0));
+        // the focused window always stays in the layout
+        assert_eq!(vec![
+            Some(Geometry::new(0, 0, 50, 100)),
+            None,
+            None,
+            Some(Geometry::new(50, 0, 50, 100)),
+            None,
+        ], views(&[Modifier::LimitWindows(2)], 5, 3));
+        assert_eq!(vec![
+            Some(Geometry::new(50, 0, 50, 100)),
+            None,
+            Some(Geometry::new(0, 0, 50, 100)),
+        ], views(&[Modifier::LimitWindows(2), Modifier::ReflectHorizontal], 3, 2));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Modifier::Magnify(1.5), Modifier::try_from("magnify:1.5").unwrap());
+        assert_eq!(Modifier::LimitWindows(3), Modifier::try_from("limit_windows:3").unwrap());
+        assert!(Modifier::try_from("magnify").is_err());
+        assert!(Modifier::try_from("magnify:0.5").is_err());
+        assert!(Modifier::try_from("magnify:-2").is_err());
+        assert!(Modifier::try_from("magnify:NaN").is_err());
+        assert!(Modifier::try_from("magnify:inf").is_err());
+        assert!(Modifier::try_from("limit_windows:0").is_err());
+    }
+}
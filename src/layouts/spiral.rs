@@ -0,0 +1,103 @@
+use crate::window::{Window, Geometry};
+use crate::stack::Stack;
+
+pub fn handle_layout<W>(view: &Geometry, windows: Stack<Window<W>>, ratio: f32) -> Stack<Window<W>> {
+    split_layout(view, windows, ratio, |pos| pos % 4)
+}
+
+pub fn handle_dwindle_layout<W>(view: &Geometry, windows: Stack<Window<W>>, ratio: f32) -> Stack<Window<W>> {
+    split_layout(view, windows, ratio, |pos| pos % 2)
+}
+
+/// Gives each window `ratio` of the remaining area, the side it takes being
+/// picked by `side`: 0 left, 1 top, 2 right, 3 bottom.
+fn split_layout<W, F: Fn(usize) -> usize>(view: &Geometry, windows: Stack<Window<W>>, ratio: f32, side: F) -> Stack<Window<W>> {
+    let window_count = windows.len();
+    let mut remaining = view.clone();
+    windows.into_iter().enumerate()
+        .map(|(pos, (is_current, window))| {
+            if pos == window_count - 1 {
+                return (is_current, window.set_view(remaining.clone()).visible(true));
+            }
+            let width = (remaining.size.width as f32 * ratio) as u32;
+            let height = (remaining.size.height as f32 * ratio) as u32;
+            let Geometry { position, size } = remaining.clone();
+            let (taken, left) = match side(pos) {
+                0 => (
+                    Geometry::new(position.x, position.y, width, size.height),
+                    Geometry::new(position.x + width as i32, position.y, size.width - width, size.height),
+                ),
+                1 => (
+                    Geometry::new(position.x, position.y, size.width, height),
+                    Geometry::new(position.x, position.y + height as i32, size.width, size.height - height),
+                ),
+                2 => (
+                    Geometry::new(position.x + (size.width - width) as i32, position.y, width, size.height),
+                    Geometry::new(position.x, position.y, size.width - width, size.height),
+                ),
+                _ => (
+                    Geometry::new(position.x, position.y + (size.height - height) as i32, size.width, height),
+                    Geometry::new(position.x, position.y, size.width, size.height - height),
+                ),
+            };
+            remaining = left;
+            (is_current, window.set_view(taken).visible(true))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod spiral_tests {
+    use crate::layouts::spiral;
+    use crate::layouts::Layout;
+    use crate::window::{Window, Geometry, WindowType};
+    use crate::stack::Stack;
+    use std::convert::TryFrom;
+
+    fn views(windows: Stack<Window<u32>>) -> Vec<Geometry> {
+        windows.iter().filter_map(|window| window.get_view().cloned()).collect()
+    }
+
+    fn windows(count: u32) -> Stack<Window<u32>> {
+        (0..count).map(|id| (id == 0, Window::new(id, WindowType::Normal))).collect()
+    }
+
+    #[test]
+    fn test() {
+        let view = Geometry::new(0, 0, 100, 100);
+        assert_eq!(vec![view.clone()], views(spiral::handle_layout(&view, windows(1), 0.5)));
+        assert_eq!(vec![
+            Geometry::new(0, 0, 50, 100),
+            Geometry::new(50, 0, 50, 100),
+        ], views(spiral::handle_layout(&view, windows(2), 0.5)));
+        assert_eq!(vec![
+            Geometry::new(0, 0, 50, 100),
+            Geometry::new(50, 0, 50, 50),
+            Geometry::new(50, 50, 50, 50),
+        ], views(spiral::handle_layout(&view, windows(3), 0.5)));
+        assert_eq!(vec![
+            Geometry::new(0, 0, 60, 100),
+            Geometry::new(60, 0, 40, 60),
+            Geometry::new(76, 60, 24, 40),
+            Geometry::new(60, 76, 16, 24),
+            Geometry::new(60, 60, 16, 16),
+        ], views(spiral::handle_layout(&view, windows(5), 0.6)));
+        assert_eq!(vec![
+            Geometry::new(0, 0, 50, 100),
+            Geometry::new(50, 0, 50, 50),
+            Geometry::new(50, 50, 25, 50),
+            Geometry::new(75, 50, 25, 25),
+            Geometry::new(75, 75, 25, 25),
+        ], views(spiral::handle_dwindle_layout(&view, windows(5), 0.5)));
+    }
+
+    #[test]
+    fn test_ratio() {
+        assert_eq!(Layout::Spiral { ratio: 0.6 }, Layout::try_from("spiral:0.6").unwrap());
+        assert_eq!(Layout::Dwindle { ratio: 0.5 }, Layout::try_from("dwindle").unwrap());
+        assert!(Layout::try_from("spiral:1.5").is_err());
+        assert!(Layout::try_from("grid:0.5").is_err());
+        assert_eq!(Layout::Spiral { ratio: 0.9 }, Layout::Spiral { ratio: 0.8 }.change_ratio(0.3));
+        assert_eq!(Layout::Grid, Layout::Grid.change_ratio(0.1));
+    }
+}
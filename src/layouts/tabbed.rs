@@ -0,0 +1,35 @@
+use crate::window::{Window, Geometry};
+use crate::stack::Stack;
+use crate::layouts::fullscreen;
+
+pub const TAB_BAR_HEIGHT: u32 = 18;
+
+pub fn tab_bar_view(view: &Geometry) -> Geometry {
+    Geometry::new(view.position.x, view.position.y, view.size.width, TAB_BAR_HEIGHT.min(view.size.height))
+}
+
+pub fn handle_layout<W>(view: &Geometry, windows: Stack<Window<W>>) -> Stack<Window<W>> {
+    let bar_height = TAB_BAR_HEIGHT.min(view.size.height);
+    let view = Geometry::new(view.position.x, view.position.y + bar_height as i32, view.size.width, view.size.height - bar_height);
+    fullscreen::handle_layout(&view, windows)
+}
+
+#[cfg(test)]
+mod tabbed_tests {
+    use crate::layouts::tabbed;
+    use crate::window::{Window, Geometry, WindowType};
+    use crate::stack::Stack;
+
+    #[test]
+    fn test() {
+        let view = Geometry::new(10, 20, 100, 100);
+        assert_eq!(Geometry::new(10, 20, 100, 18), tabbed::tab_bar_view(&view));
+        for count in [1, 2, 3, 5].iter() {
+            let windows: Stack<Window<u32>> = (0..*count).map(|id| (id == 1 % count, Window::new(id, WindowType::Normal))).collect();
+            let windows = tabbed::handle_layout(&view, windows);
+            // every window is below the tab bar, only the focused one is shown
+            assert!(windows.iter().all(|window| window.get_view() == Some(&Geometry::new(10, 38, 100, 82))));
+            assert_eq!(vec![1 % count], windows.iter().filter(|window| window.is_visible()).map(|window| **window).collect::<Vec<_>>());
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use crate::window::{Window, Geometry};
+use crate::stack::Stack;
+
+pub fn handle_layout<W>(view: &Geometry, windows: Stack<Window<W>>, master_count: usize, ratio: f32, centred: bool) -> Stack<Window<W>> {
+    let window_count = windows.len();
+    if window_count == 0 { return windows; };
+    let master_count = master_count.clamp(1, window_count);
+    let stack_count = window_count - master_count;
+    let master_width = if stack_count == 0 { view.size.width } else { (view.size.width as f32 * ratio) as u32 };
+    let side_width = if stack_count > 1 { (view.size.width - master_width) / 2 } else { view.size.width - master_width };
+    let right_count = (stack_count + 1) / 2;
+    let left_count = stack_count / 2;
+    let (master_x, first_x, second_x) = if centred && stack_count > 1 {
+        (side_width, side_width + master_width, 0)
+    } else {
+        (0, master_width, master_width + side_width)
+    };
+    windows.into_iter().enumerate()
+        .map(|(pos, (is_current, window))| {
+            let view = if pos < master_count {
+                column(view, master_x, master_width, master_count, pos)
+            } else if (pos - master_count) % 2 == 0 || stack_count == 1 {
+                column(view, first_x, side_width, right_count, (pos - master_count) / 2)
+            } else {
+                column(view, second_x, side_width, left_count, (pos - master_count) / 2)
+            };
+            (is_current, window.set_view(view).visible(true))
+        })
+        .collect()
+}
+
+fn column(view: &Geometry, x: u32, width: u32, count: usize, index: usize) -> Geometry {
+    let height = view.size.height / count as u32;
+    let y = height * index as u32;
+    Geometry::new(view.position.x + x as i32, view.position.y + y as i32, width, height)
+}
+
+#[cfg(test)]
+mod three_column_tests {
+    use crate::layouts::three_column;
+    use crate::layouts::Layout;
+    use crate::window::{Window, Geometry, WindowType};
+    use crate::stack::Stack;
+    use std::convert::TryFrom;
+
+    fn views(windows: Stack<Window<u32>>) -> Vec<Geometry> {
+        windows.iter().filter_map(|window| window.get_view().cloned()).collect()
+    }
+
+    fn windows(count: u32) -> Stack<Window<u32>> {
+        (0..count).map(|id| (id == 0, Window::new(id, WindowType::Normal))).collect()
+    }
+
+    #[test]
+    fn test() {
+        let view = Geometry::new(0, 0, 120, 100);
+        assert_eq!(vec![view.clone()], views(three_column::handle_layout(&view, windows(1), 1, 0.5, true)));
+        assert_eq!(vec![
+            Geometry::new(0, 0, 60, 100),
+            Geometry::new(60, 0, 60, 100),
+        ], views(three_column::handle_layout(&view, windows(2), 1, 0.5, true)));
+        assert_eq!(vec![
+            Geometry::new(30, 0, 60, 100),
+            Geometry::new(90, 0, 30, 100),
+            Geometry::new(0, 0, 30, 100),
+        ], views(three_column::handle_layout(&view, windows(3), 1, 0.5, true)));
+        assert_eq!(vec![
+            Geometry::new(0, 0, 60, 100),
+            Geometry::new(60, 0, 30, 100),
+            Geometry::new(90, 0, 30, 100),
+        ], views(three_column::handle_layout(&view, windows(3), 1, 0.5, false)));
+        // the stack windows alternate between the right and left columns
+        assert_eq!(vec![
+            Geometry::new(30, 0, 60, 100),
+            Geometry::new(90, 0, 30, 50),
+            Geometry::new(0, 0, 30, 50),
+            Geometry::new(90, 50, 30, 50),
+            Geometry::new(0, 50, 30, 50),
+        ], views(three_column::handle_layout(&view, windows(5), 1, 0.5, true)));
+    }
+
+    #[test]
+    fn test_master() {
+        let view = Geometry::new(0, 0, 100, 100);
+        assert_eq!(vec![
+            Geometry::new(20, 0, 60, 50),
+            Geometry::new(20, 50, 60, 50),
+            Geometry::new(80, 0, 20, 50),
+            Geometry::new(0, 0, 20, 100),
+            Geometry::new(80, 50, 20, 50),
+        ], views(three_column::handle_layout(&view, windows(5), 2, 0.6, true)));
+        assert_eq!(Layout::ThreeColumn { master_count: 2, ratio: 0.6 }, Layout::try_from("three_column:2:0.6").unwrap());
+        assert_eq!(Layout::ThreeColumnLeft { master_count: 3, ratio: 0.5 }, Layout::try_from("three_column_left:3").unwrap());
+        assert!(Layout::try_from("three_column:0").is_err());
+        let layout = Layout::ThreeColumn { master_count: 1, ratio: 0.5 };
+        assert_eq!(Layout::ThreeColumn { master_count: 2, ratio: 0.5 }, layout.clone().change_master_count(1));
+        assert_eq!(layout.clone(), layout.change_master_count(-1));
+    }
+}
@@ -1,11 +1,16 @@
+mod error;
 mod window;
 mod workspace;
 mod screen;
 mod layouts;
 mod state;
 mod stack;
-mod keys;
+pub mod keys;
 mod command;
 pub mod config;
 pub mod manager;
 pub mod displays;
+pub mod snapshot;
+pub mod session;
+
+pub use error::{Error, Result};
@@ -1,10 +1,169 @@
 use rwm::config::Config;
 use rwm::manager::Manager;
+use rwm::Error;
+use rwm::snapshot::Snapshot;
 use simple_logger;
 use rwm::displays::xcb_server::XcbDisplayServer;
+use std::convert::TryFrom;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, fs, process};
 
-#[tokio::main]
+const USAGE: &str = "Usage: rwm [options]
+
+Options:
+    --config <path>      read the config from <path>
+    --check-config       validate the config and exit
+    --display <name>     connect to the X display <name>
+    --replace            replace the running window manager
+    --log-level <level>  one of error, warn, info, debug, trace
+    --restore <path>     restore the windows saved by a restart
+    --command <command>  send a command to the running window manager:
+                           add_workspace <name>, rename_workspace <name>,
+                           delete_workspace, move_workspace_next,
+                           move_workspace_previous, save_session <name>,
+                           restore_session <name>
+    --version            print the version and exit
+    --help               print this help and exit";
+
+#[derive(Default)]
+struct Args {
+    config: Option<PathBuf>,
+    check_config: bool,
+    display: Option<String>,
+    replace: bool,
+    log_level: Option<log::Level>,
+    restore: Option<PathBuf>,
+    command: Option<String>,
+    version: bool,
+    help: bool,
+}
+
+impl Args {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> rwm::Result<Self> {
+        let mut parsed = Args::default();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| Error::InvalidArgument(format!("missing value for {}", arg)));
+            match arg.as_str() {
+                "--config" => parsed.config = Some(PathBuf::from(value()?)),
+                "--check-config" => parsed.check_config = true,
+                "--display" => parsed.display = Some(value()?),
+                "--replace" => parsed.replace = true,
+                "--log-level" => {
+                    let level = value()?;
+                    let level = level.parse().map_err(|_| Error::InvalidArgument(format!("invalid log level {:?}", level)))?;
+                    parsed.log_level = Some(level);
+                },
+                "--restore" => parsed.restore = Some(PathBuf::from(value()?)),
+                "--command" => parsed.command = Some(value()?),
+                "--version" => parsed.version = true,
+                "-h" | "--help" => parsed.help = true,
+                _ => return Err(Error::InvalidArgument(format!("unknown option {:?}\n\n{}", arg, USAGE))),
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// An explicit `--config` must exist, the default one is optional.
+    fn load_config(&self) -> rwm::Result<Config> {
+        match (&self.config, Config::default_path()) {
+            (Some(path), _) => Config::load(path),
+            (None, Some(path)) if path.exists() => Config::load(&path),
+            _ => Ok(Config::new()),
+        }
+    }
+}
+
+async fn run() -> rwm::Result<()> {
+    let args = Args::parse(env::args().skip(1))?;
+    if args.help {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+    if args.version {
+        println!("rwm {}", env!("CARGO_PKG_VERSION"));
+        return Ok(());
+    }
+    if let Some(command) = &args.command {
+        return XcbDisplayServer::send_command(args.display.as_deref(), command);
+    }
+    let config = args.load_config()?;
+    if args.check_config {
+        println!("config ok");
+        return Ok(());
+    }
+    let config = Config { display: args.display, replace: args.replace, ..config };
+
+    simple_logger::init_with_level(args.log_level.unwrap_or(log::Level::Trace))?;
+    let manager = Manager::<XcbDisplayServer>::new(config)?;
+    // the previous process is gone, starting without its windows beats not starting
+    let snapshot = args.restore.as_ref().and_then(|path| {
+        read_snapshot(path)
+            .map_err(|error| log::error!("Failed to restore the windows from {}: {}", path.display(), error))
+            .ok()
+    });
+    let manager = match snapshot {
+        Some(snapshot) => manager.restore(snapshot),
+        None => manager,
+    };
+    let state = manager.stream().await;
+    if state.restart {
+        restart(&state.snapshot())?;
+    }
+    Ok(())
+}
+
+fn read_snapshot(path: &Path) -> rwm::Result<Snapshot<xcb::Window>> {
+    let content = fs::read_to_string(path)?;
+    if let Err(error) = fs::remove_file(path) {
+        log::warn!("Failed to remove {}: {}", path.display(), error);
+    }
+    Snapshot::try_from(content.as_str())
+}
+
+/// Writes the snapshot to a new file only the user can read, in
+/// `$XDG_RUNTIME_DIR` when there is one.
+fn write_snapshot(snapshot: &Snapshot<xcb::Window>) -> rwm::Result<PathBuf> {
+    let dir = env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from).unwrap_or_else(env::temp_dir);
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.subsec_nanos()).unwrap_or_default();
+    let path = dir.join(format!("rwm-{}-{}.snapshot", process::id(), nanos));
+    let mut file = fs::OpenOptions::new().write(true).create_new(true).mode(0o600).open(&path)?;
+    file.write_all(snapshot.to_string().as_bytes())?;
+    Ok(path)
+}
+
+/// Saves the windows to a file and runs the binary again with the same
+/// arguments, telling it to restore them.
+fn restart(snapshot: &Snapshot<xcb::Window>) -> rwm::Result<()> {
+    let path = write_snapshot(snapshot)
+        .map_err(|error| log::error!("Failed to save the windows, restarting without them: {}", error))
+        .ok();
+    let mut args = env::args().skip(1);
+    let mut kept = Vec::new();
+    while let Some(arg) = args.next() {
+        if arg == "--restore" {
+            args.next();
+        } else {
+            kept.push(arg);
+        }
+    }
+    log::info!("Restart WM ...");
+    let mut command = process::Command::new(env::current_exe()?);
+    command.args(kept);
+    if let Some(path) = path {
+        command.arg("--restore").arg(path);
+    }
+    let error = command.exec();
+    Err(error.into())
+}
+
+#[tokio::main(basic_scheduler)]
 async fn main() {
-    simple_logger::init().unwrap();
-    Manager::<XcbDisplayServer>::new(Config::new()).stream().await;
+    if let Err(error) = run().await {
+        eprintln!("rwm: {}", error);
+        process::exit(1);
+    }
 }
@@ -1,21 +1,41 @@
 use crate::config::Config;
 use crate::state::State;
+use crate::workspace::Workspace;
 use crate::displays::DisplayServer;
 use std::collections::HashMap;
 use crate::command::Command;
 use futures::StreamExt;
+use std::ops::Deref;
+use std::process;
+use crate::error::Result;
+use crate::snapshot::Snapshot;
+use crate::session::{Session, SessionRequest, WindowIdentity};
+use crate::window::WindowProperties;
 
 pub struct Manager<D: DisplayServer> {
     config: Config,
     display: D,
     handlers: HashMap<D::KeyCombo, Command>,
+    button_handlers: HashMap<u8, Command>,
+    snapshot: Option<Snapshot<D::Window>>,
 }
 
 impl<D: DisplayServer> Manager<D> {
-    pub fn new(config: Config) -> Self {
-        let display = D::new(&config);
+    pub fn new(config: Config) -> Result<Self> {
+        let display = D::new(&config)?;
+        Ok(Self::with_display(config, display))
+    }
+
+    pub fn with_display(config: Config, display: D) -> Self {
         let handlers = Command::new(&config);
-        Manager { config, display, handlers }
+        let button_handlers = Command::new_buttons();
+        Manager { config, display, handlers, button_handlers, snapshot: None }
+    }
+
+    /// Puts the windows back as they were saved before a restart.
+    pub fn restore(mut self, snapshot: Snapshot<D::Window>) -> Self {
+        self.snapshot = Some(snapshot);
+        self
     }
 
     fn update(&self, state: &State<D::Window>) {
@@ -23,6 +43,14 @@ impl<D: DisplayServer> Manager<D> {
             log::debug!("Close WM ...");
             self.display.quit()
         } else {
+            if let Some(view) = &state.warp_pointer {
+                self.display.warp_pointer(view);
+            }
+            if state.workspaces_changed {
+                let names = state.workspaces.iter().map(|w| w.get_name()).collect::<Vec<_>>();
+                log::debug!("Update workspaces {:?}", &names);
+                self.display.set_workspaces(&names, state.workspaces.get_current_index().unwrap_or(0));
+            }
             state.workspaces.iter()
                 .filter(|&w| w.is_changed())
                 .for_each(|workspace| {
@@ -31,21 +59,248 @@ impl<D: DisplayServer> Manager<D> {
                         .for_each(|window| {
                             self.display.configure_window(window);
                             self.display.set_visibility(&window, window.is_visible());
-                        })
+                        });
+                    if let Some(window) = workspace.get_current() {
+                        self.display.raise_window(window);
+                    }
+                    self.update_tab_bar(workspace);
                 });
+            let current = state.workspaces.get_current();
+            if state.workspaces_changed || current.is_some_and(|workspace| workspace.is_changed()) {
+                let focused = current.and_then(|workspace| workspace.get_current());
+                self.display.focus_window(focused.map(|window| window.deref()));
+            }
+        }
+    }
+
+    /// The identity of the managed windows, from the properties they keep
+    /// up to date and the command of their process.
+    fn identities(&self, state: &State<D::Window>) -> Vec<(D::Window, WindowIdentity)> {
+        state.workspaces.iter()
+            .flat_map(|workspace| workspace.iter())
+            .map(|window| {
+                let WindowProperties { class, instance, title, .. } = window.get_properties().clone();
+                let command = self.display.get_command(window.deref());
+                (window.deref().clone(), WindowIdentity { class, instance, title, command })
+            })
+            .collect()
+    }
+
+    /// Saves or restores a named session, telling the windows apart by their
+    /// identity on the display.
+    fn handle_session(&self, state: State<D::Window>) -> State<D::Window> {
+        let dir = match (&state.session_request, &self.config.session_dir) {
+            (None, _) => return state,
+            (Some(_), Some(dir)) => dir,
+            (Some(_), None) => {
+                log::error!("No directory to keep sessions in");
+                return state;
+            },
+        };
+        match state.session_request.clone() {
+            Some(SessionRequest::Save(name)) => {
+                log::info!("Save session {}", &name);
+                let identities = self.identities(&state);
+                let session = Session(state.snapshot().map(|window| {
+                    identities.iter().find(|(w, _)| w == window).map(|(_, identity)| identity.clone()).unwrap_or_default()
+                }));
+                if let Err(error) = session.save(dir, &name) {
+                    log::error!("Failed to save session {}: {}", &name, error);
+                }
+                state
+            },
+            Some(SessionRequest::Restore(name)) => match Session::load(dir, &name) {
+                Ok(session) => {
+                    log::info!("Restore session {}", &name);
+                    let (snapshot, missing) = session.match_windows(&self.identities(&state));
+                    let launched = missing.into_iter()
+                        .filter(|_| self.config.launch_missing_windows)
+                        .filter(|(_, identity)| match identity.command.split_first() {
+                            Some((program, args)) => process::Command::new(program).args(args).spawn()
+                                .map_err(|error| log::error!("Failed to launch {}: {}", program, error))
+                                .is_ok(),
+                            None => false,
+                        })
+                        .collect();
+                    state.arrange(snapshot).expect_windows(launched)
+                },
+                Err(error) => {
+                    log::error!("Failed to restore session {}: {}", &name, error);
+                    state
+                },
+            },
+            None => state,
         }
     }
 
-    pub async fn stream(self) -> State<D::Window> {
+    fn update_tab_bar(&self, workspace: &Workspace<D::Window>) {
+        match workspace.get_tab_bar() {
+            Some(view) if workspace.is_shown() && !workspace.is_empty() => {
+                let focused = workspace.get_current().map(|w| w.deref());
+                let tabs = workspace.iter()
+                    .map(|window| (window.deref().clone(), window.get_properties().title.clone(), Some(window.deref()) == focused))
+                    .collect::<Vec<_>>();
+                self.display.show_tab_bar(workspace.get_name(), &view, &tabs);
+            },
+            _ => self.display.hide_tab_bar(workspace.get_name()),
+        }
+    }
+
+    pub async fn stream(mut self) -> State<D::Window> {
         log::info!("Start WM ...");
-        let state = State::new(&self.config);
+        let state = State::new(&self.config).adopt(self.display.get_windows(), self.snapshot.take());
         self.display.clone()
             .fold(state, |state, event| async {
                 log::debug!("Received event {:?}", &event);
-                let state = state.handle_event(event, &self.handlers);
+                let state = state.handle_event(event, &self.handlers, &self.button_handlers);
+                let state = self.handle_session(state);
                 self.update(&state);
                 state.reset()
             })
             .await
     }
 }
+
+#[cfg(test)]
+mod manager_tests {
+    use crate::config::Config;
+    use crate::displays::Event;
+    use crate::displays::mock::{MockDisplayServer, Call};
+    use crate::keys::{KeyCombo, Key, ModKey};
+    use crate::manager::Manager;
+    use crate::window::{Geometry, WindowType, WindowProperties};
+    use crate::snapshot::{Snapshot, WorkspaceSnapshot};
+    use crate::layouts::Layout;
+    use crate::layouts::manual::SplitTree;
+    use std::convert::TryFrom;
+    use futures::executor::block_on;
+
+    fn key(mod_keys: Vec<ModKey>, key: char) -> Event<u32, KeyCombo> {
+        Event::KeyPressed(KeyCombo { mod_keys, key: Key(key) })
+    }
+
+    fn properties(title: &str) -> WindowProperties {
+        WindowProperties { class: "XTerm".to_string(), instance: "xterm".to_string(), title: title.to_string(), role: String::new() }
+    }
+
+    fn workspaces(current: usize) -> Call {
+        Call::SetWorkspaces((1..=9).map(|i| i.to_string()).collect(), current)
+    }
+
+    #[test]
+    fn test() {
+        let display = MockDisplayServer::with_events(vec![
+            Event::ScreenAdded(1, Geometry::new(0, 0, 100, 100)),
+            Event::WindowAdded(10, WindowType::Normal),
+            Event::WindowAdded(11, WindowType::Normal),
+            key(vec![ModKey::Mod4], '2'),
+            key(vec![ModKey::Mod4, ModKey::Shift], 'q'),
+            Event::WindowAdded(12, WindowType::Normal),
+        ]);
+        let state = block_on(Manager::with_display(Config::new(), display.clone()).stream());
+        let calls = display.calls();
+
+        assert!(state.quit);
+        assert_eq!(Some(&Call::Quit), calls.last());
+        assert!(calls.iter().all(|call| !matches!(call, Call::ConfigureWindow(12, _))));
+
+        let switched = calls.iter().position(|call| *call == workspaces(1)).unwrap();
+        assert_eq!(&[
+            Call::ConfigureWindow(11, Geometry::new(0, 0, 50, 100)),
+            Call::SetVisibility(11, true),
+            Call::ConfigureWindow(10, Geometry::new(50, 0, 50, 100)),
+            Call::SetVisibility(10, true),
+            Call::RaiseWindow(11),
+            Call::HideTabBar("1".to_string()),
+            Call::FocusWindow(Some(11)),
+        ], &calls[switched - 7..switched]);
+        assert_eq!(&[
+            Call::ConfigureWindow(11, Geometry::new(0, 0, 50, 100)),
+            Call::SetVisibility(11, false),
+            Call::ConfigureWindow(10, Geometry::new(50, 0, 50, 100)),
+            Call::SetVisibility(10, false),
+            Call::RaiseWindow(11),
+            Call::HideTabBar("1".to_string()),
+            Call::HideTabBar("2".to_string()),
+            Call::FocusWindow(None),
+            Call::Quit,
+        ], &calls[switched + 1..]);
+    }
+
+    #[test]
+    fn test_restore() {
+        let snapshot = Snapshot {
+            workspaces: vec![
+                WorkspaceSnapshot { name: "web".to_string(), layout: 1, layouts: Vec::new(), tree: SplitTree::try_from("v(1:0,2:1,1:2)").unwrap(), focused: Some(11), windows: vec![10, 11, 12] },
+                WorkspaceSnapshot { name: "code".to_string(), layout: 0, layouts: Vec::new(), tree: SplitTree::new(), focused: None, windows: vec![13] },
+            ],
+            screens: vec!["code".to_string()],
+            current: Some("code".to_string()),
+        };
+        let display = MockDisplayServer::with_events(vec![
+            Event::ScreenAdded(1, Geometry::new(0, 0, 100, 100)),
+            key(vec![ModKey::Mod4], 'q'),
+        ]).with_windows(vec![(10, false), (11, false), (13, true), (14, true), (15, false)]);
+        let state = block_on(Manager::with_display(Config::new(), display.clone()).restore(snapshot.clone()).stream());
+
+        assert!(state.restart);
+        let layouts = Config::new().layouts;
+        assert_eq!(Snapshot {
+            workspaces: vec![
+                WorkspaceSnapshot { name: "web".to_string(), layout: 1, layouts: layouts.clone(), tree: SplitTree::try_from("v(1:0,2:1)").unwrap(), focused: Some(11), windows: vec![10, 11] },
+                WorkspaceSnapshot { name: "code".to_string(), layout: 0, layouts, tree: SplitTree::try_from("h(1:1,1:0)").unwrap(), focused: Some(14), windows: vec![14, 13] },
+            ],
+            screens: vec!["code".to_string()],
+            current: Some("code".to_string()),
+        }, state.snapshot());
+        assert!(display.calls().contains(&Call::ConfigureWindow(14, Geometry::new(0, 0, 50, 100))));
+    }
+
+    #[test]
+    fn test_window_properties() {
+        let display = MockDisplayServer::with_events(vec![
+            Event::ScreenAdded(1, Geometry::new(0, 0, 100, 100)),
+            Event::WindowAdded(10, WindowType::Normal),
+            Event::WindowPropertyChanged(10, properties("~")),
+            Event::WindowPropertyChanged(10, properties("make")),
+            Event::WindowPropertyChanged(11, properties("unmanaged")),
+            key(vec![ModKey::Mod4, ModKey::Shift], 'q'),
+        ]);
+        let state = block_on(Manager::with_display(Config::new(), display).stream());
+        let window = state.workspaces.iter().flat_map(|workspace| workspace.iter()).next().unwrap();
+        assert_eq!(&properties("make"), window.get_properties());
+    }
+
+    #[test]
+    fn test_session() {
+        let dir = std::env::temp_dir().join(format!("rwm-sessions-{}", std::process::id()));
+        let config = Config { session_dir: Some(dir.clone()), layouts: vec![Layout::Spiral { ratio: 0.5 }], ..Config::new() };
+        let display = MockDisplayServer::with_events(vec![
+            Event::ScreenAdded(1, Geometry::new(0, 0, 100, 100)),
+            Event::WindowAdded(10, WindowType::Normal),
+            Event::WindowAdded(11, WindowType::Normal),
+            Event::WindowAdded(12, WindowType::Normal),
+            Event::WindowPropertyChanged(10, properties("top")),
+            Event::WindowPropertyChanged(11, properties("make")),
+            Event::WindowPropertyChanged(12, properties("vim")),
+            key(vec![ModKey::Mod4], 'l'),
+            key(vec![ModKey::Mod4, ModKey::Shift], 'l'),
+            key(vec![ModKey::Mod4, ModKey::Shift], 's'),
+            key(vec![ModKey::Mod4], 'h'),
+            key(vec![ModKey::Mod4, ModKey::Shift], 'h'),
+            key(vec![ModKey::Mod4, ModKey::Shift], 'j'),
+            key(vec![ModKey::Mod4], '2'),
+            key(vec![ModKey::Mod4, ModKey::Shift], 'o'),
+            key(vec![ModKey::Mod4, ModKey::Shift], 'q'),
+        ]);
+        let state = block_on(Manager::with_display(config, display).stream());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let snapshot = state.snapshot();
+        assert_eq!(Some("1".to_string()), snapshot.current);
+        let workspace = &snapshot.workspaces[0];
+        assert_eq!((Some(12), &vec![12, 11, 10]), (workspace.focused, &workspace.windows));
+        assert_eq!(vec![Layout::Spiral { ratio: 0.5 }.change_ratio(0.05)], workspace.layouts);
+        assert_eq!("h(1:10,1:11,1.1:12)", workspace.tree.filter_map(|index| workspace.windows.get(*index).cloned()).to_string());
+    }
+}
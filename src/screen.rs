@@ -2,20 +2,38 @@ use crate::window::Geometry;
 
 pub struct Screen<W> {
     window: W,
-    workspace: usize,
+    workspace: String,
     view: Geometry,
 }
 
 impl<W> Screen<W> {
-    pub fn new(window: W, view: Geometry) -> Self {
+    pub fn new(window: W, view: Geometry, workspace: String) -> Self {
         Screen {
             window,
-            workspace: 0,
+            workspace,
             view
         }
     }
 
+    pub fn get_window(&self) -> &W {
+        &self.window
+    }
+
+    pub fn set_view(mut self, view: Geometry) -> Self {
+        self.view = view;
+        self
+    }
+
     pub fn get_view(&self) -> &Geometry {
         &self.view
     }
+
+    pub fn get_workspace(&self) -> &str {
+        &self.workspace
+    }
+
+    pub fn set_workspace(mut self, workspace: String) -> Self {
+        self.workspace = workspace;
+        self
+    }
 }
@@ -0,0 +1,215 @@
+use crate::error::{Error, Result};
+use crate::layouts::Layout;
+use crate::layouts::manual::SplitTree;
+use crate::snapshot::{Snapshot, WorkspaceSnapshot};
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+use std::fs;
+use std::path::Path;
+
+/// What tells a window apart across sessions, its id changes every time.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct WindowIdentity {
+    pub class: String,
+    pub instance: String,
+    pub title: String,
+    /// Command line of the owning process, to launch it again.
+    pub command: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionRequest {
+    Save(String),
+    Restore(String),
+}
+
+/// A named snapshot of the workspaces, saved to disk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session(pub Snapshot<WindowIdentity>);
+
+impl Session {
+    pub fn save(&self, dir: &Path, name: &str) -> Result<()> {
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join(name), self.to_string())?;
+        Ok(())
+    }
+
+    pub fn load(dir: &Path, name: &str) -> Result<Self> {
+        let content = fs::read_to_string(dir.join(name))?;
+        Self::try_from(content.as_str())
+    }
+
+    /// Finds the saved windows among the given ones. Same class, instance and
+    /// title are matched first, then only same class and instance. Returns
+    /// the matched arrangement and the saved windows without a match, with
+    /// the name of their workspace.
+    pub fn match_windows<W: Clone>(&self, windows: &[(W, WindowIdentity)]) -> (Snapshot<W>, Vec<(String, WindowIdentity)>) {
+        let saved = self.0.workspaces.iter().flat_map(|workspace| workspace.windows.iter()).collect::<Vec<_>>();
+        let mut matches: Vec<Option<usize>> = vec![None; saved.len()];
+        let passes: [fn(&WindowIdentity, &WindowIdentity) -> bool; 2] = [
+            |saved, window| saved.class == window.class && saved.instance == window.instance && saved.title == window.title,
+            |saved, window| saved.class == window.class && saved.instance == window.instance,
+        ];
+        for is_match in passes.iter() {
+            for (index, identity) in saved.iter().enumerate() {
+                if matches[index].is_some() {
+                    continue;
+                }
+                matches[index] = windows.iter()
+                    .enumerate()
+                    .position(|(candidate, (_, window))| !matches.contains(&Some(candidate)) && is_match(identity, window));
+            }
+        }
+
+        let mut matched = matches.into_iter().map(|index| index.map(|index| windows[index].0.clone()));
+        let mut missing = Vec::new();
+        let workspaces = self.0.workspaces.iter()
+            .map(|workspace| {
+                let mut focused = None;
+                let mut indices = Vec::new();
+                let windows = workspace.windows.iter()
+                    .filter_map(|identity| {
+                        let window = matched.next().flatten();
+                        if window.is_none() {
+                            missing.push((workspace.name.clone(), identity.clone()));
+                        }
+                        if workspace.focused.as_ref() == Some(identity) {
+                            focused = window.clone();
+                        }
+                        indices.push(window.as_ref().map(|_| indices.iter().flatten().count()));
+                        window
+                    })
+                    .collect();
+                let tree = workspace.tree.filter_map(|index| indices.get(*index).cloned().flatten());
+                WorkspaceSnapshot { name: workspace.name.clone(), layout: workspace.layout, layouts: workspace.layouts.clone(), tree, focused, windows }
+            })
+            .collect();
+        (Snapshot { workspaces, screens: Vec::new(), current: self.0.current.clone() }, missing)
+    }
+}
+
+/// A `workspace` line with its layouts and split tree as in a snapshot, followed by a `window` line per window, each optionally
+/// followed by the `command` of its process. Fields are tab separated.
+impl Display for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let clean = |text: &str| text.replace(&['\t', '\n'][..], " ");
+        if let Some(current) = &self.0.current {
+            writeln!(f, "current\t{}", current)?;
+        }
+        self.0.workspaces.iter().try_for_each(|workspace| {
+            let layouts = workspace.layouts.iter().map(|layout| layout.to_string()).collect::<Vec<_>>();
+            writeln!(f, "workspace\t{}\t{}\t{}\t{}", workspace.name, workspace.layout, layouts.join(" "), workspace.tree)?;
+            workspace.windows.iter().try_for_each(|window| {
+                let focused = if workspace.focused.as_ref() == Some(window) { 1 } else { 0 };
+                writeln!(f, "window\t{}\t{}\t{}\t{}", focused, clean(&window.class), clean(&window.instance), clean(&window.title))?;
+                if !window.command.is_empty() {
+                    let command = window.command.iter().map(|arg| clean(arg)).collect::<Vec<_>>();
+                    writeln!(f, "command\t{}", command.join("\t"))?;
+                }
+                Ok(())
+            })
+        })
+    }
+}
+
+impl TryFrom<&str> for Session {
+    type Error = Error;
+
+    fn try_from(content: &str) -> std::result::Result<Self, Self::Error> {
+        let invalid = |line: &str| Error::InvalidSnapshot(line.to_string());
+        content.lines()
+            .filter(|line| !line.is_empty())
+            .try_fold(Snapshot { workspaces: Vec::new(), screens: Vec::new(), current: None }, |mut snapshot, line| {
+                let fields = line.split('\t').collect::<Vec<_>>();
+                let workspace = snapshot.workspaces.last_mut();
+                match (fields.as_slice(), workspace) {
+                    (["current", name], _) => snapshot.current = Some(name.to_string()),
+                    (["workspace", name, layout, layouts, tree], _) => snapshot.workspaces.push(WorkspaceSnapshot {
+                        name: name.to_string(),
+                        layout: layout.parse().map_err(|_| invalid(line))?,
+                        layouts: layouts.split_whitespace().map(Layout::try_from).collect::<Result<_>>().map_err(|_| invalid(line))?,
+                        tree: SplitTree::try_from(*tree).map_err(|_| invalid(line))?,
+                        focused: None,
+                        windows: Vec::new(),
+                    }),
+                    (["window", focused, class, instance, title], Some(workspace)) => {
+                        let window = WindowIdentity {
+                            class: class.to_string(),
+                            instance: instance.to_string(),
+                            title: title.to_string(),
+                            command: Vec::new(),
+                        };
+                        if *focused == "1" {
+                            workspace.focused = Some(window.clone());
+                        }
+                        workspace.windows.push(window);
+                    },
+                    (["command", command @ ..], Some(workspace)) if !command.is_empty() => {
+                        let command = command.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+                        let window = workspace.windows.last_mut().ok_or_else(|| invalid(line))?;
+                        if workspace.focused.as_ref() == Some(window) {
+                            workspace.focused = Some(WindowIdentity { command: command.clone(), ..window.clone() });
+                        }
+                        window.command = command;
+                    },
+                    _ => return Err(invalid(line)),
+                }
+                Ok(snapshot)
+            })
+            .map(Session)
+    }
+}
+
+#[cfg(test)]
+mod session_tests {
+    use crate::layouts::Layout;
+    use crate::layouts::manual::SplitTree;
+    use crate::session::{Session, WindowIdentity};
+    use crate::snapshot::{Snapshot, WorkspaceSnapshot};
+    use std::convert::TryFrom;
+
+    fn identity(class: &str, title: &str) -> WindowIdentity {
+        WindowIdentity { class: class.to_string(), instance: class.to_lowercase(), title: title.to_string(), command: Vec::new() }
+    }
+
+    #[test]
+    fn test() {
+        let firefox = WindowIdentity { command: vec!["firefox".to_string(), "--new-window".to_string()], ..identity("Firefox", "News") };
+        let session = Session(Snapshot {
+            workspaces: vec![
+                WorkspaceSnapshot {
+                    name: "web".to_string(),
+                    layout: 0,
+                    layouts: vec![Layout::Spiral { ratio: 0.6 }],
+                    tree: SplitTree::try_from("h(1:0,1.5:1)").unwrap(),
+                    focused: Some(firefox.clone()),
+                    windows: vec![identity("Firefox", "Mail"), firefox],
+                },
+                WorkspaceSnapshot {
+                    name: "code".to_string(),
+                    layout: 1,
+                    layouts: vec![Layout::Tall, Layout::Manual],
+                    tree: SplitTree::try_from("v(1:0,2:1)").unwrap(),
+                    focused: None,
+                    windows: vec![identity("Emacs", "main.rs"), identity("XTerm", "make")],
+                },
+            ],
+            screens: Vec::new(),
+            current: Some("code".to_string()),
+        });
+        assert_eq!(session, Session::try_from(session.to_string().as_str()).unwrap());
+
+        let windows = vec![
+            (1, identity("Firefox", "News")),
+            (2, identity("Emacs", "lib.rs")),
+            (3, identity("Firefox", "Search")),
+        ];
+        let (snapshot, missing) = session.match_windows(&windows);
+        let windows = snapshot.workspaces.iter().map(|workspace| (workspace.focused, workspace.windows.clone())).collect::<Vec<_>>();
+        assert_eq!(vec![(Some(1), vec![3, 1]), (None, vec![2])], windows);
+        let trees = snapshot.workspaces.iter().map(|workspace| workspace.tree.to_string()).collect::<Vec<_>>();
+        assert_eq!(vec!["h(1:0,1.5:1)", "0"], trees);
+        assert_eq!(vec![Layout::Tall, Layout::Manual], snapshot.workspaces[1].layouts);
+        assert_eq!(vec![("code".to_string(), identity("XTerm", "make"))], missing);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::error::Error;
+use crate::layouts::Layout;
+use crate::layouts::manual::SplitTree;
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+/// The arrangement of the windows kept across a restart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot<W> {
+    pub workspaces: Vec<WorkspaceSnapshot<W>>,
+    /// The workspace shown on each screen, in screen order.
+    pub screens: Vec<String>,
+    pub current: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkspaceSnapshot<W> {
+    pub name: String,
+    pub layout: usize,
+    /// Every layout of the workspace, with its parameters.
+    pub layouts: Vec<Layout>,
+    /// The split tree of the manual layout, over the indices of `windows`.
+    pub tree: SplitTree<usize>,
+    pub focused: Option<W>,
+    pub windows: Vec<W>,
+}
+
+impl<W> Snapshot<W> {
+    pub fn map<V, F: Fn(&W) -> V>(&self, f: F) -> Snapshot<V> {
+        let workspaces = self.workspaces.iter()
+            .map(|workspace| WorkspaceSnapshot {
+                name: workspace.name.clone(),
+                layout: workspace.layout,
+                layouts: workspace.layouts.clone(),
+                tree: workspace.tree.clone(),
+                focused: workspace.focused.as_ref().map(&f),
+                windows: workspace.windows.iter().map(&f).collect(),
+            })
+            .collect();
+        Snapshot { workspaces, screens: self.screens.clone(), current: self.current.clone() }
+    }
+}
+
+/// One tab separated line per workspace, layouts and windows are separated
+/// by spaces.
+impl<W: Display> Display for Snapshot<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(current) = &self.current {
+            writeln!(f, "current\t{}", current)?;
+        }
+        self.screens.iter().try_for_each(|screen| writeln!(f, "screen\t{}", screen))?;
+        self.workspaces.iter().try_for_each(|workspace| {
+            let focused = workspace.focused.as_ref().map_or("-".to_string(), |window| window.to_string());
+            let layouts = workspace.layouts.iter().map(|layout| layout.to_string()).collect::<Vec<_>>();
+            let windows = workspace.windows.iter().map(|window| window.to_string()).collect::<Vec<_>>();
+            writeln!(f, "workspace\t{}\t{}\t{}\t{}\t{}\t{}", workspace.name, workspace.layout, layouts.join(" "), workspace.tree, focused, windows.join(" "))
+        })
+    }
+}
+
+impl<W: FromStr> TryFrom<&str> for Snapshot<W> {
+    type Error = Error;
+
+    fn try_from(content: &str) -> Result<Self, Self::Error> {
+        let invalid = |line: &str| Error::InvalidSnapshot(line.to_string());
+        let window = |line: &str, window: &str| window.parse().map_err(|_| invalid(line));
+        content.lines()
+            .filter(|line| !line.is_empty())
+            .try_fold(Snapshot { workspaces: Vec::new(), screens: Vec::new(), current: None }, |mut snapshot, line| {
+                match line.split('\t').collect::<Vec<_>>().as_slice() {
+                    ["current", name] => snapshot.current = Some(name.to_string()),
+                    ["screen", name] => snapshot.screens.push(name.to_string()),
+                    ["workspace", name, layout, layouts, tree, focused, windows] => snapshot.workspaces.push(WorkspaceSnapshot {
+                        name: name.to_string(),
+                        layout: layout.parse().map_err(|_| invalid(line))?,
+                        layouts: layouts.split_whitespace().map(Layout::try_from).collect::<Result<_, _>>().map_err(|_| invalid(line))?,
+                        tree: SplitTree::try_from(*tree).map_err(|_| invalid(line))?,
+                        focused: if *focused == "-" { None } else { Some(window(line, focused)?) },
+                        windows: windows.split_whitespace().map(|w| window(line, w)).collect::<Result<_, _>>()?,
+                    }),
+                    _ => return Err(invalid(line)),
+                }
+                Ok(snapshot)
+            })
+    }
+}
+
+#[cfg(test)]
+mod snapshot_tests {
+    use crate::layouts::Layout;
+    use crate::layouts::manual::SplitTree;
+    use crate::snapshot::{Snapshot, WorkspaceSnapshot};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn test() {
+        let layouts = vec![Layout::Tall, Layout::ThreeColumn { master_count: 2, ratio: 0.6 }, Layout::Manual];
+        let tree = SplitTree::try_from("h(1:0,1.5:1)").unwrap();
+        let snapshot = Snapshot {
+            workspaces: vec![
+                WorkspaceSnapshot { name: "web".to_string(), layout: 1, layouts, tree, focused: Some(3), windows: vec![2, 3] },
+                WorkspaceSnapshot { name: "empty".to_string(), layout: 0, layouts: vec![Layout::Grid], tree: SplitTree::new(), focused: None, windows: vec![] },
+            ],
+            screens: vec!["empty".to_string(), "web".to_string()],
+            current: Some("web".to_string()),
+        };
+        let content = snapshot.to_string();
+        assert_eq!("current\tweb\nscreen\tempty\nscreen\tweb\n\
+            workspace\tweb\t1\ttall three_column:2:0.6 manual\th(1:0,1.5:1)\t3\t2 3\n\
+            workspace\tempty\t0\tgrid\t-\t-\t\n", content);
+        assert_eq!(snapshot, Snapshot::try_from(content.as_str()).unwrap());
+        assert!(Snapshot::<u32>::try_from("workspace\tweb\t0\ttall\t-\tx\t").is_err());
+        assert!(Snapshot::<u32>::try_from("workspace\tweb\t0\tspiral:2\t-\t-\t").is_err());
+    }
+}
@@ -1,6 +1,8 @@
 use std::fmt::Debug;
 use std::iter::FromIterator;
 use std::ops::Deref;
+use std::convert::TryFrom;
+use crate::error::Error;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Stack<I> {
@@ -8,6 +10,29 @@ pub struct Stack<I> {
     stack: Vec<I>
 }
 
+/// Which item gets the focus when the focused item is removed. The stack does
+/// not keep a focus history, so `MostRecent` behaves as `Previous` here and is
+/// refined by callers that track one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusPolicy {
+    Previous,
+    Next,
+    MostRecent,
+}
+
+impl TryFrom<&str> for FocusPolicy {
+    type Error = Error;
+
+    fn try_from(policy: &str) -> Result<Self, Self::Error> {
+        match policy {
+            "previous" => Ok(FocusPolicy::Previous),
+            "next" => Ok(FocusPolicy::Next),
+            "most_recent" => Ok(FocusPolicy::MostRecent),
+            _ => Err(Error::InvalidFocusPolicy(policy.to_string())),
+        }
+    }
+}
+
 pub struct StackIntoIter<I> {
     count: usize,
     current: Option<usize>,
@@ -86,6 +111,19 @@ impl<I> Stack<I> {
         self
     }
 
+    pub fn update_at<F: FnOnce(I) -> I>(mut self, index: usize, replace: F) -> Self {
+        if index < self.stack.len() {
+            let old_item = self.stack.remove(index);
+            let new_item = replace(old_item);
+            self.stack.insert(index, new_item);
+        }
+        self
+    }
+
+    pub fn get_current_index(&self) -> Option<usize> {
+        self.current
+    }
+
     pub fn get_current(&self) -> Option<&I> {
         self.current.as_ref()
             .and_then(|current| self.stack.get(*current))
@@ -98,6 +136,12 @@ impl<I> Stack<I> {
         self
     }
 
+    pub fn push(mut self, item: I) -> Self {
+        self.stack.push(item);
+        self.current = self.current.or(Some(0));
+        self
+    }
+
     pub fn set_current(mut self, index: usize) -> Self {
         if self.stack.len() > index {
             self.current = Some(index);
@@ -105,10 +149,22 @@ impl<I> Stack<I> {
         self
     }
 
-    pub fn remove_by<F>(mut self, predicate: F) -> Self where F: Fn(&I) -> bool {
-        self.stack.iter()
-            .position(|item| predicate(item))
-            .map(|item| self.stack.remove(item));
+    pub fn remove_by<F>(mut self, predicate: F, policy: FocusPolicy) -> Self where F: Fn(&I) -> bool {
+        let index = match self.stack.iter().position(|item| predicate(item)) {
+            Some(index) => index,
+            None => return self,
+        };
+        self.stack.remove(index);
+        let len = self.stack.len();
+        self.current = match self.current {
+            _ if len == 0 => None,
+            Some(current) if current > index => Some(current - 1),
+            Some(current) if current == index => match policy {
+                FocusPolicy::Next => Some(if index == len { 0 } else { index }),
+                FocusPolicy::Previous | FocusPolicy::MostRecent => Some(if index == 0 { len - 1 } else { index - 1 }),
+            },
+            current => current,
+        };
         self
     }
 
@@ -125,11 +181,46 @@ impl<I> Stack<I> {
         });
         self
     }
+
+    pub fn swap_next(mut self) -> Self {
+        if let Some(current) = self.current {
+            let next = if current == self.stack.len() - 1 { 0 } else { current + 1 };
+            self.stack.swap(current, next);
+            self.current = Some(next);
+        }
+        self
+    }
+
+    pub fn swap_previous(mut self) -> Self {
+        if let Some(current) = self.current {
+            let previous = if current == 0 { self.stack.len() - 1 } else { current - 1 };
+            self.stack.swap(current, previous);
+            self.current = Some(previous);
+        }
+        self
+    }
+
+    pub fn swap_with_head(mut self) -> Self {
+        if let Some(current) = self.current {
+            self.stack.swap(current, 0);
+            self.current = Some(0);
+        }
+        self
+    }
+
+    pub fn rotate(mut self) -> Self {
+        if !self.stack.is_empty() {
+            self.stack.rotate_right(1);
+            self.current = self.current.map(|current| (current + 1) % self.stack.len());
+        }
+        self
+    }
 }
 
 #[cfg(test)]
 mod stack_tests {
-    use crate::stack::Stack;
+    use crate::stack::{Stack, FocusPolicy};
+    use proptest::prelude::*;
 
     #[test]
     fn test() {
@@ -142,4 +233,140 @@ mod stack_tests {
         let expected: Stack<i32> = vec![3, 2, 1].into();
         assert_eq!(expected, stack);
     }
+
+    #[test]
+    fn test_swap() {
+        let stack: Stack<i32> = vec![1, 2, 3].into();
+        let stack = stack.swap_next();
+        assert_eq!(vec![2, 1, 3], *stack);
+        assert_eq!(Some(&1), stack.get_current());
+        let stack = stack.swap_previous().swap_previous();
+        assert_eq!(vec![3, 2, 1], *stack);
+        assert_eq!(Some(&1), stack.get_current());
+        let stack = stack.swap_with_head();
+        assert_eq!(vec![1, 2, 3], *stack);
+        assert_eq!(Some(&1), stack.get_current());
+        let stack = stack.rotate();
+        assert_eq!(vec![3, 1, 2], *stack);
+        assert_eq!(Some(&1), stack.get_current());
+    }
+
+    #[test]
+    fn test_remove() {
+        let stack: Stack<i32> = vec![1, 2, 3].into();
+        let stack = stack.set_current(1).remove_by(|item| *item == 2, FocusPolicy::Previous);
+        assert_eq!(Some(&1), stack.get_current());
+        let stack = stack.remove_by(|item| *item == 1, FocusPolicy::Next);
+        assert_eq!(Some(&3), stack.get_current());
+        let stack = stack.remove_by(|item| *item == 3, FocusPolicy::Next);
+        assert_eq!(None, stack.get_current());
+        assert!(stack.is_empty());
+    }
+
+    #[derive(Debug, Clone)]
+    enum Operation {
+        Add(u8),
+        Remove(u8, FocusPolicy),
+        SetCurrent(usize),
+        Next,
+        Previous,
+        SwapNext,
+        SwapPrevious,
+        SwapWithHead,
+        Rotate,
+    }
+
+    fn operation() -> impl Strategy<Value=Operation> {
+        let policy = prop_oneof![
+            Just(FocusPolicy::Previous),
+            Just(FocusPolicy::Next),
+            Just(FocusPolicy::MostRecent),
+        ];
+        prop_oneof![
+            any::<u8>().prop_map(Operation::Add),
+            (any::<u8>(), policy).prop_map(|(item, policy)| Operation::Remove(item, policy)),
+            (0..8usize).prop_map(Operation::SetCurrent),
+            Just(Operation::Next),
+            Just(Operation::Previous),
+            Just(Operation::SwapNext),
+            Just(Operation::SwapPrevious),
+            Just(Operation::SwapWithHead),
+            Just(Operation::Rotate),
+        ]
+    }
+
+    fn apply(stack: Stack<u8>, operation: Operation) -> Stack<u8> {
+        match operation {
+            Operation::Add(item) => stack.add(item),
+            Operation::Remove(item, policy) => stack.remove_by(|i| *i == item, policy),
+            Operation::SetCurrent(index) => stack.set_current(index),
+            Operation::Next => stack.next(),
+            Operation::Previous => stack.previous(),
+            Operation::SwapNext => stack.swap_next(),
+            Operation::SwapPrevious => stack.swap_previous(),
+            Operation::SwapWithHead => stack.swap_with_head(),
+            Operation::Rotate => stack.rotate(),
+        }
+    }
+
+    fn sorted(stack: &Stack<u8>) -> Vec<u8> {
+        let mut items = stack.to_vec();
+        items.sort();
+        items
+    }
+
+    proptest! {
+        #[test]
+        fn current_is_within_bounds(items in prop::collection::vec(any::<u8>(), 0..8), operations in prop::collection::vec(operation(), 0..32)) {
+            let stack = operations.into_iter()
+                .fold(Stack::from(items), |stack, operation| {
+                    let stack = apply(stack, operation);
+                    assert_eq!(stack.is_empty(), stack.current.is_none());
+                    assert!(stack.current.map_or(true, |current| current < stack.len()));
+                    stack
+                });
+            let round_trip = stack.clone().into_iter().collect::<Stack<u8>>();
+            prop_assert_eq!(stack, round_trip);
+        }
+
+        #[test]
+        fn moving_keeps_items_and_focus(items in prop::collection::vec(any::<u8>(), 1..8), operation in operation()) {
+            let stack = Stack::from(items);
+            let focused = stack.get_current().cloned();
+            let moved = apply(stack.clone(), operation.clone());
+            match operation {
+                Operation::SwapNext | Operation::SwapPrevious | Operation::SwapWithHead | Operation::Rotate => {
+                    prop_assert_eq!(sorted(&stack), sorted(&moved));
+                    prop_assert_eq!(focused.as_ref(), moved.get_current());
+                },
+                _ => {},
+            }
+        }
+
+        #[test]
+        fn next_and_previous_are_inverse(items in prop::collection::vec(any::<u8>(), 0..8), index in 0..8usize) {
+            let stack = Stack::from(items).set_current(index);
+            prop_assert_eq!(stack.clone(), stack.clone().next().previous());
+            prop_assert_eq!(stack.clone(), stack.clone().previous().next());
+        }
+
+        #[test]
+        fn remove_keeps_order_of_other_items(items in prop::collection::vec(any::<u8>(), 1..8), index in 0..8usize, policy in prop_oneof![Just(FocusPolicy::Previous), Just(FocusPolicy::Next)]) {
+            let index = index % items.len();
+            let item = items[index];
+            let stack = Stack::from(items.clone()).set_current(index);
+            let removed = stack.remove_by(|i| *i == item, policy);
+            let mut expected = items.clone();
+            let position = expected.iter().position(|i| *i == item).unwrap();
+            expected.remove(position);
+            prop_assert_eq!(&expected, &*removed);
+            if position == index && !expected.is_empty() {
+                let focus = match policy {
+                    FocusPolicy::Next => if position == expected.len() { 0 } else { position },
+                    _ => if position == 0 { expected.len() - 1 } else { position - 1 },
+                };
+                prop_assert_eq!(Some(focus), removed.current);
+            }
+        }
+    }
 }
@@ -1,43 +1,187 @@
 use crate::workspace::Workspace;
-use crate::window::{WindowType, Window, Geometry};
+use crate::window::{WindowType, Window, Geometry, WindowProperties};
 use crate::config::Config;
 use crate::displays::Event;
-use crate::stack::Stack;
+use crate::stack::{Stack, FocusPolicy};
 use crate::layouts::Layout;
+use crate::layouts::manual::Direction;
+use crate::layouts::modifiers::Modifier;
 use crate::keys::KeyCombo;
 use std::hash::Hash;
 use std::fmt::Debug;
 use std::collections::HashMap;
 use crate::command::Command;
 use crate::screen::Screen;
+use std::ops::Deref;
+use crate::snapshot::{Snapshot, WorkspaceSnapshot};
+use crate::session::{SessionRequest, WindowIdentity};
+use std::convert::TryFrom;
 
 pub struct State<W> {
     pub quit: bool,
+    pub restart: bool,
     pub workspaces: Stack<Workspace<W>>,
     pub screens: Stack<Screen<W>>,
+    focus_history: Vec<W>,
+    history_cursor: Option<usize>,
+    pub workspaces_changed: bool,
+    pub warp_pointer: Option<Geometry>,
+    pub session_request: Option<SessionRequest>,
+    previous_workspace: Option<String>,
+    /// The workspaces the screens showed before a restart, and whether each
+    /// was the current one, handed out as the screens come back.
+    restored_screens: Vec<(String, bool)>,
+    /// Windows launched by a restored session, with their saved workspace.
+    pending_windows: Vec<(String, WindowIdentity)>,
+    config: Config,
 }
 
 impl<W: Debug + Clone + Eq> State<W> {
     pub fn new(config: &Config) -> Self {
-        let layouts: Stack<Layout> = config.layouts.clone().into();
         let workspaces = config.workspaces.iter()
-            .map(|name| Workspace::new(name.clone(), Stack::new(), layouts.clone()))
+            .map(|name| Self::new_workspace(config, name.clone(), Stack::new()))
             .collect::<Vec<Workspace<W>>>()
             .into();
 
-        Self { quit: false, workspaces, screens: Stack::new() }
+        Self {
+            quit: false,
+            restart: false,
+            workspaces,
+            screens: Stack::new(),
+            focus_history: Vec::new(),
+            history_cursor: None,
+            workspaces_changed: true,
+            warp_pointer: None,
+            session_request: None,
+            previous_workspace: None,
+            restored_screens: Vec::new(),
+            pending_windows: Vec::new(),
+            config: config.clone(),
+        }
+    }
+
+    /// Moves the windows of a snapshot to their saved workspace, in front of
+    /// the windows already there. Workspaces missing are created again, the
+    /// others keep their modifiers, split tree and focus history.
+    pub fn arrange(mut self, snapshot: Snapshot<W>) -> Self {
+        for saved in snapshot.workspaces {
+            let windows = saved.windows.iter()
+                .filter_map(|id| self.workspaces.iter().flat_map(|workspace| workspace.iter()).find(|window| ***window == *id).cloned())
+                .collect::<Vec<_>>();
+            self = windows.iter().fold(self, |state, window| state.remove_window(window.deref().clone()));
+            let position = self.workspace_position(&saved.name);
+            let windows = match position {
+                Some(position) => windows.into_iter().chain(self.workspaces[position].iter().cloned()).collect(),
+                None => windows,
+            };
+            let focused = windows.iter().position(|window| Some(window.deref()) == saved.focused.as_ref()).unwrap_or(0);
+            let windows = windows.into_iter().enumerate().map(|(index, window)| (index == focused, window)).collect();
+            let tree = saved.tree.filter_map(|index| saved.windows.get(*index).cloned());
+            let restore = |workspace: Workspace<W>| workspace.set_layouts(&saved.layouts).set_layout(saved.layout).set_tree(tree);
+            let position = match position {
+                Some(position) => {
+                    self.workspaces = self.workspaces.update_at(position, |workspace| restore(workspace.set_windows(windows)));
+                    position
+                },
+                None => {
+                    let workspace = restore(Self::new_workspace(&self.config, saved.name.clone(), windows));
+                    self.workspaces = self.workspaces.push(workspace);
+                    self.workspaces.len() - 1
+                },
+            };
+            if let Some(screen) = self.screen_of(&saved.name) {
+                self = self.show_workspace(screen, position);
+            }
+        }
+        self.workspaces_changed = true;
+        match snapshot.current.and_then(|name| self.workspace_position(&name)) {
+            Some(position) if self.workspaces.get_current_index() != Some(position) => self.goto_workspace(position),
+            _ => self,
+        }
+    }
+
+    /// Places the next windows with the class and instance of the given ones
+    /// on their workspace, once their class is known.
+    pub fn expect_windows(mut self, windows: Vec<(String, WindowIdentity)>) -> Self {
+        self.pending_windows = windows;
+        self
+    }
+
+    pub fn request_session(mut self, request: SessionRequest) -> Self {
+        self.session_request = Some(request);
+        self
+    }
+
+    fn new_workspace(config: &Config, name: String, windows: Stack<Window<W>>) -> Workspace<W> {
+        let layouts: Stack<Layout> = config.layouts.clone().into();
+        let modifiers = config.modifiers.get(&name).cloned().unwrap_or_default();
+        Workspace::new(name, windows, layouts, modifiers, config.focus_policy)
+    }
+
+    pub fn snapshot(&self) -> Snapshot<W> {
+        let workspaces = self.workspaces.iter()
+            .map(|workspace| WorkspaceSnapshot {
+                name: workspace.get_name().to_string(),
+                layout: workspace.get_layout_index().unwrap_or(0),
+                layouts: workspace.get_layouts(),
+                tree: workspace.get_tree().filter_map(|window| workspace.iter().position(|w| w.deref() == window)),
+                focused: workspace.get_current().map(|window| window.deref().clone()),
+                windows: workspace.iter().map(|window| window.deref().clone()).collect(),
+            })
+            .collect();
+        let screens = self.screens.iter().map(|screen| screen.get_workspace().to_string()).collect();
+        let current = self.workspaces.get_current().map(|workspace| workspace.get_name().to_string());
+        Snapshot { workspaces, screens, current }
+    }
+
+    /// Manages the windows already on the display. The ones in the snapshot
+    /// go back to their workspace, the other mapped ones to the current one.
+    pub fn adopt(mut self, windows: Vec<(W, bool)>, snapshot: Option<Snapshot<W>>) -> Self {
+        let mut remaining = windows;
+        if let Some(snapshot) = snapshot.filter(|snapshot| !snapshot.workspaces.is_empty()) {
+            self.restored_screens = snapshot.screens.iter()
+                .map(|name| (name.clone(), Some(name) == snapshot.current.as_ref()))
+                .collect();
+            let config = &self.config;
+            self.workspaces = snapshot.workspaces.into_iter()
+                .map(|saved| {
+                    let tree = saved.tree.filter_map(|index| saved.windows.get(*index).cloned());
+                    let windows = saved.windows.into_iter()
+                        .filter(|window| remaining.iter().any(|(w, _)| w == window))
+                        .collect::<Vec<_>>();
+                    remaining.retain(|(w, _)| !windows.contains(w));
+                    let focused = saved.focused.filter(|window| windows.contains(window)).or_else(|| windows.first().cloned());
+                    let windows = windows.into_iter()
+                        .map(|window| (Some(&window) == focused.as_ref(), Window::new(window, WindowType::Normal).visible(true)))
+                        .collect();
+                    let workspace = Self::new_workspace(config, saved.name, windows)
+                        .set_layouts(&saved.layouts)
+                        .set_layout(saved.layout)
+                        .set_tree(tree);
+                    (false, workspace)
+                })
+                .collect();
+            let current = snapshot.current.and_then(|name| self.workspace_position(&name)).unwrap_or(0);
+            self.workspaces = self.workspaces.set_current(current);
+        }
+        remaining.into_iter()
+            .filter(|(_, is_mapped)| *is_mapped)
+            .fold(self, |state, (window, _)| state.add_window(window, WindowType::Normal))
     }
 
     pub fn reset(mut self) -> Self {
+        self.workspaces_changed = false;
+        self.warp_pointer = None;
+        self.session_request = None;
         self.workspaces = self.workspaces.into_iter()
             .map(|(is_current, workspace)| (is_current, workspace.reset()))
             .collect();
         self
     }
 
-    pub fn handle_event<K: From<KeyCombo> + Hash + Eq + Debug>(self, event: Event<W, K>, handlers: &HashMap<K, Command>) -> Self {
+    pub fn handle_event<K: From<KeyCombo> + Hash + Eq + Debug>(self, event: Event<W, K>, handlers: &HashMap<K, Command>, button_handlers: &HashMap<u8, Command>) -> Self {
         log::debug!("Handling event {:?}", event);
-        match event {
+        let state = match event {
             Event::WindowAdded(window, window_type) => {
                 self.add_window(window, window_type)
             },
@@ -45,16 +189,98 @@ impl<W: Debug + Clone + Eq> State<W> {
                 self.remove_window(window)
             },
             Event::KeyPressed(key) => {
-                self.key_pressed(key, handlers)
+                self.run_binding(key, handlers)
+            },
+            Event::RootButtonPressed(button) => {
+                self.run_binding(button, button_handlers)
             },
             Event::ScreenAdded(window, view) => {
                 self.add_screen(window, view)
-            }
+            },
+            Event::ScreenRemoved(window) => {
+                self.remove_screen(window)
+            },
+            Event::ScreenChanged(window, view) => {
+                self.change_screen(window, view)
+            },
+            Event::WindowFocused(window) => {
+                self.focus_window(window)
+            },
+            Event::WindowPropertyChanged(window, properties) => {
+                self.set_properties(window, properties)
+            },
+            Event::CommandReceived(command) => match Command::try_from(command.as_str()) {
+                Ok(command) => command.execute(self),
+                Err(error) => {
+                    log::warn!("{}", error);
+                    self
+                },
+            },
             _ => self
+        };
+        state.record_focus()
+    }
+
+    fn focused_window(&self) -> Option<&W> {
+        self.workspaces.get_current()?
+            .get_current()
+            .map(|window| window.deref())
+    }
+
+    /// Moves the focused window to the top of the history, unless the focus
+    /// comes from walking the history.
+    fn record_focus(mut self) -> Self {
+        let focused = match self.focused_window() {
+            Some(window) => window.clone(),
+            None => return self,
+        };
+        let cursor = self.history_cursor.and_then(|cursor| self.focus_history.get(cursor));
+        if cursor == Some(&focused) {
+            return self;
+        }
+        self.history_cursor = None;
+        self.focus_history.retain(|w| w != &focused);
+        self.focus_history.push(focused);
+        self
+    }
+
+    fn focus_anywhere(self, window: W) -> Self {
+        let contains = |workspace: &Workspace<W>| workspace.iter().any(|w| w.deref() == &window);
+        let in_current = self.workspaces.get_current().is_some_and(contains);
+        match self.workspaces.iter().position(contains) {
+            Some(_) if in_current => self.focus_window(window),
+            Some(index) => self.goto_workspace(index).focus_window(window),
+            None => self,
+        }
+    }
+
+    pub fn focus_last(self) -> Self {
+        let len = self.focus_history.len();
+        match len.checked_sub(2).and_then(|index| self.focus_history.get(index)).cloned() {
+            Some(window) => self.focus_anywhere(window),
+            None => self,
         }
     }
 
-    fn key_pressed<K: From<KeyCombo> + Hash + Eq + Debug>(self, key: K, handlers: &HashMap<K, Command>) -> Self {
+    pub fn focus_history_back(mut self) -> Self {
+        let cursor = self.history_cursor.unwrap_or(self.focus_history.len().saturating_sub(1));
+        if cursor == 0 { return self; }
+        self.history_cursor = Some(cursor - 1);
+        let window = self.focus_history[cursor - 1].clone();
+        self.focus_anywhere(window)
+    }
+
+    pub fn focus_history_forward(mut self) -> Self {
+        let cursor = match self.history_cursor {
+            Some(cursor) if cursor + 1 < self.focus_history.len() => cursor + 1,
+            _ => return self,
+        };
+        self.history_cursor = Some(cursor);
+        let window = self.focus_history[cursor].clone();
+        self.focus_anywhere(window)
+    }
+
+    fn run_binding<K: Hash + Eq>(self, key: K, handlers: &HashMap<K, Command>) -> Self {
         if let Some(command) = handlers.get(&key) {
             command.execute(self)
         } else {
@@ -62,25 +288,309 @@ impl<W: Debug + Clone + Eq> State<W> {
         }
     }
 
+    /// Shows the workspace the screen had before a restart, the current one
+    /// on the first screen, or else the first hidden one.
     fn add_screen(mut self, window: W, view: Geometry) -> Self {
-        self.screens = self.screens.add(Screen::new(window, view));
-        self.update_workspace_view()
+        let restored = if self.restored_screens.is_empty() { None } else { Some(self.restored_screens.remove(0)) };
+        let restored = restored
+            .and_then(|(name, is_current)| Some((self.workspace_position(&name)?, is_current)))
+            .filter(|(position, _)| self.screen_of(self.workspaces[*position].get_name()).is_none());
+        let (position, is_current) = match restored {
+            Some((position, is_current)) => (Some(position), is_current || self.screens.is_empty()),
+            None if self.screens.is_empty() => (self.workspaces.get_current_index(), false),
+            None => (self.hidden_workspace(), false),
+        };
+        match position {
+            Some(position) => {
+                let name = self.workspaces[position].get_name().to_string();
+                self.screens = self.screens.push(Screen::new(window, view, name));
+                self.workspaces_changed = true;
+                let screen = self.screens.len() - 1;
+                if is_current {
+                    self.workspaces = self.workspaces.set_current(position);
+                    self.screens = self.screens.set_current(screen);
+                }
+                self.show_workspace(screen, position)
+            },
+            None => {
+                log::warn!("No workspace left to show on screen {:?}", window);
+                self
+            },
+        }
+    }
+
+    /// Hides the workspace of a removed screen, unless it was the focused one
+    /// which then moves to the screen getting the focus.
+    fn remove_screen(mut self, window: W) -> Self {
+        let screen = match self.screens.iter().position(|screen| screen.get_window() == &window) {
+            Some(screen) => screen,
+            None => return self,
+        };
+        let was_current = self.screens.get_current_index() == Some(screen);
+        let name = self.screens[screen].get_workspace().to_string();
+        log::debug!("Remove screen {:?} showing workspace {}", &window, &name);
+        self.workspaces_changed = true;
+        self.screens = self.screens.remove_by(|screen| screen.get_window() == &window, FocusPolicy::Previous);
+        let position = self.workspace_position(&name);
+        let current_screen = self.screens.get_current_index();
+        match (position, current_screen) {
+            (Some(position), Some(current_screen)) if was_current => {
+                let replaced = self.workspace_position(self.screens[current_screen].get_workspace());
+                if let Some(replaced) = replaced {
+                    self.workspaces = self.workspaces.update_at(replaced, |workspace| workspace.visible(false));
+                }
+                let mut state = self.show_workspace(current_screen, position);
+                state.workspaces = state.workspaces.set_current(position);
+                state
+            },
+            (Some(position), _) => {
+                self.workspaces = self.workspaces.update_at(position, |workspace| workspace.visible(false));
+                if let Some(current_screen) = current_screen {
+                    let current = self.workspace_position(self.screens[current_screen].get_workspace());
+                    if let Some(current) = current {
+                        self.workspaces = self.workspaces.set_current(current);
+                    }
+                }
+                self
+            },
+            (None, _) => self,
+        }
+    }
+
+    fn change_screen(mut self, window: W, view: Geometry) -> Self {
+        let screen = match self.screens.iter().position(|screen| screen.get_window() == &window) {
+            Some(screen) => screen,
+            None => return self,
+        };
+        log::debug!("Change screen {:?} to {:?}", &window, &view);
+        self.screens = self.screens.update_at(screen, |screen| screen.set_view(view));
+        match self.workspace_position(self.screens[screen].get_workspace()) {
+            Some(position) => self.show_workspace(screen, position),
+            None => self,
+        }
+    }
+
+    pub fn focus_screen(mut self, screen: usize) -> Self {
+        if screen >= self.screens.len() || self.screens.get_current_index() == Some(screen) {
+            return self;
+        }
+        log::debug!("Focus screen {}", screen);
+        self.screens = self.screens.set_current(screen);
+        if let Some(position) = self.workspace_position(self.screens[screen].get_workspace()) {
+            self.workspaces = self.workspaces.set_current(position);
+        }
+        self.workspaces_changed = true;
+        if self.config.warp_pointer {
+            self.warp_pointer = Some(self.screens[screen].get_view().clone());
+        }
+        self
+    }
+
+    pub fn cycle_screen(self, forward: bool) -> Self {
+        let len = self.screens.len();
+        match self.screens.get_current_index() {
+            Some(current) if len > 1 => {
+                let screen = if forward { (current + 1) % len } else { (current + len - 1) % len };
+                self.focus_screen(screen)
+            },
+            _ => self,
+        }
+    }
+
+    pub fn move_window_to_screen(mut self, screen: usize) -> Self {
+        if screen >= self.screens.len() || self.screens.get_current_index() == Some(screen) {
+            return self;
+        }
+        let position = match self.workspace_position(self.screens[screen].get_workspace()) {
+            Some(position) => position,
+            None => return self,
+        };
+        let mut window = None;
+        self.workspaces = self.workspaces.update_current(|workspace| {
+            let (workspace, current_window) = workspace.take_current_window();
+            window = current_window;
+            workspace
+        });
+        if let Some(window) = window {
+            log::debug!("Move window {:?} to screen {}", window.deref(), screen);
+            self.workspaces = self.workspaces.update_at(position, |workspace| workspace.add_window(window));
+        }
+        self
     }
 
-    fn update_workspace_view(mut self) -> Self {
-        if let Some(screen) = self.screens.get_current() {
-            self.workspaces = self.workspaces
-                .update_current(|workspace| workspace.set_view(screen.get_view().clone()))
+    pub fn swap_workspaces_with_screen(self, screen: usize) -> Self {
+        let current_screen = match self.screens.get_current_index() {
+            Some(current_screen) if screen < self.screens.len() && current_screen != screen => current_screen,
+            _ => return self,
+        };
+        let current = self.workspace_position(self.screens[current_screen].get_workspace());
+        let other = self.workspace_position(self.screens[screen].get_workspace());
+        match (current, other) {
+            (Some(current), Some(other)) => {
+                let mut state = self.show_workspace(screen, current).show_workspace(current_screen, other);
+                state.workspaces = state.workspaces.set_current(other);
+                state.workspaces_changed = true;
+                state
+            },
+            _ => self,
         }
+    }
+
+    fn screen_of(&self, workspace: &str) -> Option<usize> {
+        self.screens.iter().position(|screen| screen.get_workspace() == workspace)
+    }
+
+    fn hidden_workspace(&self) -> Option<usize> {
+        self.workspaces.iter().position(|workspace| self.screen_of(workspace.get_name()).is_none())
+    }
+
+    fn show_workspace(mut self, screen: usize, position: usize) -> Self {
+        let name = self.workspaces[position].get_name().to_string();
+        let view = self.screens[screen].get_view().clone();
+        self.screens = self.screens.update_at(screen, |screen| screen.set_workspace(name));
+        self.workspaces = self.workspaces.update_at(position, |workspace| workspace.set_view(view).visible(true));
         self
     }
 
+    fn workspace_position(&self, name: &str) -> Option<usize> {
+        self.workspaces.iter().position(|workspace| workspace.get_name() == name)
+    }
+
     pub fn goto_workspace(mut self, position: usize) -> Self {
-        self.workspaces = self.workspaces
-            .update_current(|workspace| workspace.visible(false))
-            .set_current(position)
-            .update_current(|workspace| workspace.visible(true));
-        self.update_workspace_view()
+        if self.workspaces.get_current_index() == Some(position) {
+            let previous = self.previous_workspace.as_ref()
+                .and_then(|previous| self.workspace_position(previous));
+            return match previous {
+                Some(previous) if self.config.workspace_auto_back_and_forth => self.goto_workspace(previous),
+                _ => self,
+            };
+        }
+        if position >= self.workspaces.len() {
+            return self;
+        }
+        self.previous_workspace = self.workspaces.get_current().map(|w| w.get_name().to_string());
+        self.workspaces_changed = true;
+        let current = self.workspaces.get_current_index();
+        let current_screen = self.screens.get_current_index();
+        let target_screen = self.screen_of(self.workspaces[position].get_name());
+        let mut state = match (current, current_screen, target_screen) {
+            (Some(current), Some(current_screen), Some(target_screen)) if self.config.swap_visible_workspaces => {
+                self.show_workspace(target_screen, current).show_workspace(current_screen, position)
+            },
+            (_, _, Some(target_screen)) => {
+                self.screens = self.screens.set_current(target_screen);
+                self
+            },
+            (_, Some(current_screen), None) => {
+                self.workspaces = self.workspaces.update_current(|workspace| workspace.visible(false));
+                self.show_workspace(current_screen, position)
+            },
+            (_, None, None) => self,
+        };
+        state.workspaces = state.workspaces.set_current(position);
+        state
+    }
+
+    pub fn goto_previous_workspace(self) -> Self {
+        let previous = self.previous_workspace.as_ref()
+            .and_then(|previous| self.workspace_position(previous));
+        match previous {
+            Some(previous) => self.goto_workspace(previous),
+            None => self,
+        }
+    }
+
+    /// Tabs and newlines separate the fields and lines of a snapshot, so
+    /// workspace names cannot have them.
+    fn is_valid_name(name: &str) -> bool {
+        if name.contains(&['\t', '\n'][..]) {
+            log::warn!("Invalid workspace name {:?}", name);
+            return false;
+        }
+        true
+    }
+
+    pub fn add_workspace(mut self, name: String) -> Self {
+        if !Self::is_valid_name(&name) {
+            return self;
+        }
+        if let Some(position) = self.workspace_position(&name) {
+            return self.goto_workspace(position);
+        }
+        log::debug!("Add workspace {}", &name);
+        let workspace = Self::new_workspace(&self.config, name, Stack::new());
+        self.workspaces = self.workspaces.push(workspace);
+        let position = self.workspaces.len() - 1;
+        self.goto_workspace(position)
+    }
+
+    pub fn rename_workspace(mut self, name: String) -> Self {
+        if !Self::is_valid_name(&name) || self.workspace_position(&name).is_some() {
+            return self;
+        }
+        self.workspaces_changed = true;
+        let old_name = self.workspaces.get_current().map(|workspace| workspace.get_name().to_string());
+        if self.previous_workspace.is_some() && self.previous_workspace == old_name {
+            self.previous_workspace = Some(name.clone());
+        }
+        let screen_name = name.clone();
+        self.screens = self.screens.update_current(|screen| screen.set_workspace(screen_name));
+        self.workspaces = self.workspaces.update_current(|workspace| workspace.rename(name));
+        self
+    }
+
+    /// Deletes the current workspace when it has no windows, its screen then
+    /// shows the first workspace not shown anywhere else.
+    pub fn delete_workspace(mut self) -> Self {
+        let current = self.workspaces.get_current_index();
+        let replacement = (0..self.workspaces.len())
+            .find(|index| Some(*index) != current && self.screen_of(self.workspaces[*index].get_name()).is_none());
+        let (name, replacement) = match (self.workspaces.get_current(), replacement) {
+            (Some(workspace), Some(replacement)) if workspace.is_empty() => {
+                (workspace.get_name().to_string(), self.workspaces[replacement].get_name().to_string())
+            },
+            _ => return self,
+        };
+        log::debug!("Delete workspace {}", &name);
+        if self.previous_workspace.as_ref() == Some(&name) {
+            self.previous_workspace = None;
+        }
+        self.workspaces_changed = true;
+        self.workspaces = self.workspaces.remove_by(|workspace| workspace.get_name() == name, FocusPolicy::Previous);
+        let position = self.workspace_position(&replacement).unwrap();
+        let mut state = match self.screens.get_current_index() {
+            Some(screen) => self.show_workspace(screen, position),
+            None => self,
+        };
+        state.workspaces = state.workspaces.set_current(position);
+        state
+    }
+
+    pub fn move_workspace(mut self, forward: bool) -> Self {
+        self.workspaces_changed = true;
+        self.workspaces = if forward { self.workspaces.swap_next() } else { self.workspaces.swap_previous() };
+        self
+    }
+
+    /// Goes to the next or previous workspace, wrapping around and optionally
+    /// skipping the ones without windows.
+    pub fn cycle_workspace(self, forward: bool, skip_empty: bool) -> Self {
+        let len = self.workspaces.len();
+        let current = self.workspaces.get_current_index().unwrap_or(0);
+        let target = (1..len)
+            .map(|offset| if forward { (current + offset) % len } else { (current + len - offset) % len })
+            .find(|index| !skip_empty || !self.workspaces[*index].is_empty());
+        match target {
+            Some(index) => self.goto_workspace(index),
+            None => self,
+        }
+    }
+
+    pub fn goto_first_empty_workspace(self) -> Self {
+        match self.workspaces.iter().position(|workspace| workspace.is_empty()) {
+            Some(index) => self.goto_workspace(index),
+            None => self,
+        }
     }
 
     pub fn next_window(mut self) -> Self {
@@ -93,18 +603,123 @@ impl<W: Debug + Clone + Eq> State<W> {
         self
     }
 
+    pub fn swap_next_window(mut self) -> Self {
+        self.workspaces = self.workspaces.update_current(Workspace::swap_next_window);
+        self
+    }
+
+    pub fn swap_previous_window(mut self) -> Self {
+        self.workspaces = self.workspaces.update_current(Workspace::swap_previous_window);
+        self
+    }
+
+    pub fn swap_master_window(mut self) -> Self {
+        self.workspaces = self.workspaces.update_current(Workspace::swap_master_window);
+        self
+    }
+
+    pub fn rotate_windows(mut self) -> Self {
+        self.workspaces = self.workspaces.update_current(Workspace::rotate_windows);
+        self
+    }
+
+    pub fn change_ratio(mut self, delta: f32) -> Self {
+        self.workspaces = self.workspaces.update_current(|workspace| workspace.change_ratio(delta));
+        self
+    }
+
+    pub fn change_master_count(mut self, delta: isize) -> Self {
+        self.workspaces = self.workspaces.update_current(|workspace| workspace.change_master_count(delta));
+        self
+    }
+
+    pub fn preselect_split(mut self, direction: Direction) -> Self {
+        self.workspaces = self.workspaces.update_current(|workspace| workspace.preselect_split(direction));
+        self
+    }
+
+    pub fn toggle_split_direction(mut self) -> Self {
+        self.workspaces = self.workspaces.update_current(Workspace::toggle_split_direction);
+        self
+    }
+
+    pub fn resize_split(mut self, delta: f32) -> Self {
+        self.workspaces = self.workspaces.update_current(|workspace| workspace.resize_split(delta));
+        self
+    }
+
+    pub fn move_split_window(mut self, forward: bool) -> Self {
+        self.workspaces = self.workspaces.update_current(|workspace| workspace.move_split_window(forward));
+        self
+    }
+
+    pub fn toggle_modifier(mut self, modifier: Modifier) -> Self {
+        self.workspaces = self.workspaces.update_current(|workspace| workspace.toggle_modifier(modifier));
+        self
+    }
+
+    pub fn focus_window(mut self, window: W) -> Self {
+        self.workspaces = self.workspaces.update_current(|workspace| workspace.focus_window(window));
+        self
+    }
+
     pub fn add_window(mut self, window: W, window_type: WindowType) -> Self {
         let window = Window::new(window, window_type).visible(true);
         self.workspaces = self.workspaces.update_current(move |workspace| workspace.add_window(window));
         self
     }
 
+    pub fn set_properties(mut self, window: W, properties: WindowProperties) -> Self {
+        let managed = self.workspaces.iter().flat_map(|workspace| workspace.iter()).find(|w| ***w == window);
+        let identified = managed.is_some_and(|w| w.get_properties().class.is_empty() && !properties.class.is_empty());
+        let pending = self.pending_windows.iter()
+            .position(|(_, identity)| identity.class == properties.class && identity.instance == properties.instance)
+            .filter(|_| identified);
+        self.workspaces = self.workspaces.into_iter()
+            .map(|(is_current, workspace)| (is_current, workspace.set_properties(&window, &properties)))
+            .collect();
+        match pending {
+            Some(index) => {
+                let (name, _) = self.pending_windows.remove(index);
+                self.move_to_workspace(window, &name)
+            },
+            None => self,
+        }
+    }
+
+    fn move_to_workspace(mut self, window: W, name: &str) -> Self {
+        let position = match self.workspace_position(name) {
+            Some(position) => position,
+            None => return self,
+        };
+        let moved = self.workspaces.iter().flat_map(|workspace| workspace.iter()).find(|w| ***w == window).cloned();
+        if let Some(moved) = moved {
+            log::debug!("Move window {:?} to its saved workspace {}", &window, name);
+            self = self.remove_window(window);
+            self.workspaces = self.workspaces.update_at(position, |workspace| workspace.add_window(moved));
+        }
+        self
+    }
+
     pub fn quit(mut self) -> Self {
         self.quit = true;
         self
     }
 
+    pub fn restart(mut self) -> Self {
+        self.restart = true;
+        self.quit()
+    }
+
     pub fn remove_window(mut self, window: W) -> Self {
+        if let Some(index) = self.focus_history.iter().position(|w| w == &window) {
+            self.focus_history.remove(index);
+            self.history_cursor = match self.history_cursor {
+                Some(cursor) if cursor > index => Some(cursor - 1),
+                Some(cursor) if cursor == index => None,
+                cursor => cursor,
+            };
+        }
         self.workspaces = self.workspaces.into_iter()
             .map(|(is_current, workspace)| {
                 (is_current, workspace.remove_window(window.clone()))
@@ -113,3 +728,287 @@ impl<W: Debug + Clone + Eq> State<W> {
         self
     }
 }
+
+#[cfg(test)]
+mod state_tests {
+    use crate::command::Command;
+    use crate::config::Config;
+    use crate::displays::Event;
+    use crate::keys::{KeyCombo, Key, ModKey};
+    use crate::snapshot::{Snapshot, WorkspaceSnapshot};
+    use crate::layouts::manual::SplitTree;
+    use crate::session::{SessionRequest, WindowIdentity};
+    use crate::state::State;
+    use crate::window::{Geometry, WindowType, WindowProperties};
+
+    fn handle(state: State<u32>, events: Vec<Event<u32, KeyCombo>>) -> State<u32> {
+        let handlers = Command::new(&state.config);
+        let button_handlers = Command::new_buttons();
+        events.into_iter().fold(state, |state, event| state.handle_event(event, &handlers, &button_handlers))
+    }
+
+    fn key(mod_keys: Vec<ModKey>, key: char) -> Event<u32, KeyCombo> {
+        Event::KeyPressed(KeyCombo { mod_keys, key: Key(key) })
+    }
+
+    fn window(window: u32) -> Event<u32, KeyCombo> {
+        Event::WindowAdded(window, WindowType::Normal)
+    }
+
+    fn screen(screen: u32, x: i32) -> Event<u32, KeyCombo> {
+        Event::ScreenAdded(screen, Geometry::new(x, 0, 100, 100))
+    }
+
+    /// The current workspace and its focused window.
+    fn focus(state: &State<u32>) -> (&str, Option<u32>) {
+        let workspace = state.workspaces.get_current().unwrap();
+        (workspace.get_name(), workspace.get_current().map(|window| **window))
+    }
+
+    #[test]
+    fn test_focus_history() {
+        let state = handle(State::new(&Config::new()), vec![screen(1, 0), window(10), window(11), window(12)]);
+        assert_eq!(vec![10, 11, 12], state.focus_history);
+        let state = handle(state, vec![key(vec![ModKey::Mod4], '\t')]);
+        assert_eq!(("1", Some(11)), focus(&state));
+        let state = handle(state, vec![key(vec![ModKey::Mod4], '\t')]);
+        assert_eq!(("1", Some(12)), focus(&state));
+
+        // the most recent window is brought back from another workspace
+        let state = handle(state, vec![key(vec![ModKey::Mod4], '2'), window(13), key(vec![ModKey::Mod4], '\t')]);
+        assert_eq!(("1", Some(12)), focus(&state));
+        assert_eq!(vec![10, 11, 13, 12], state.focus_history);
+
+        // walking the history leaves it untouched
+        let state = handle(state, vec![key(vec![ModKey::Mod4], '['), key(vec![ModKey::Mod4], '[')]);
+        assert_eq!(("1", Some(11)), focus(&state));
+        assert_eq!(vec![10, 11, 13, 12], state.focus_history);
+        let state = handle(state, vec![key(vec![ModKey::Mod4], ']')]);
+        assert_eq!(("2", Some(13)), focus(&state));
+
+        let state = handle(state, vec![Event::WindowRemoved(13)]);
+        assert_eq!(vec![10, 11, 12], state.focus_history);
+    }
+
+    #[test]
+    fn test_back_and_forth() {
+        let state = handle(State::new(&Config::new()), vec![screen(1, 0), key(vec![ModKey::Mod4], '3'), key(vec![ModKey::Mod4], '3')]);
+        assert_eq!("3", focus(&state).0);
+        let state = handle(state, vec![key(vec![ModKey::Mod4], '`')]);
+        assert_eq!("1", focus(&state).0);
+        let state = handle(state, vec![key(vec![ModKey::Mod4], '`')]);
+        assert_eq!("3", focus(&state).0);
+
+        let config = Config { workspace_auto_back_and_forth: true, ..Config::new() };
+        let state = handle(State::new(&config), vec![screen(1, 0), key(vec![ModKey::Mod4], '3'), key(vec![ModKey::Mod4], '3')]);
+        assert_eq!("1", focus(&state).0);
+        assert!(state.workspaces[0].is_shown() && !state.workspaces[2].is_shown());
+        let state = handle(state, vec![key(vec![ModKey::Mod4], '1')]);
+        assert_eq!("3", focus(&state).0);
+    }
+
+    #[test]
+    fn test_cycle_workspaces() {
+        let state = handle(State::new(&Config::new()), vec![screen(1, 0), key(vec![ModKey::Mod4], ',')]);
+        assert_eq!("9", focus(&state).0);
+        let state = handle(state, vec![key(vec![ModKey::Mod4], '.')]);
+        assert_eq!("1", focus(&state).0);
+        let state = handle(state, vec![Event::RootButtonPressed(5)]);
+        assert_eq!("2", focus(&state).0);
+
+        let state = handle(state, vec![window(10), key(vec![ModKey::Mod4], '5'), window(11)]);
+        let state = handle(state, vec![key(vec![ModKey::Mod4, ModKey::Shift], '.')]);
+        assert_eq!("2", focus(&state).0);
+        let state = handle(state, vec![key(vec![ModKey::Mod4, ModKey::Shift], ',')]);
+        assert_eq!("5", focus(&state).0);
+        let state = handle(state, vec![key(vec![ModKey::Mod4], 'n')]);
+        assert_eq!("1", focus(&state).0);
+        // with every other workspace empty there is nothing to go to
+        let state = handle(state, vec![key(vec![ModKey::Mod4], '5'), Event::WindowRemoved(10)]);
+        let state = handle(state, vec![key(vec![ModKey::Mod4, ModKey::Shift], '.')]);
+        assert_eq!("5", focus(&state).0);
+    }
+
+    /// The workspace shown on each screen.
+    fn screens(state: &State<u32>) -> Vec<&str> {
+        state.screens.iter().map(|screen| screen.get_workspace()).collect()
+    }
+
+    #[test]
+    fn test_screens() {
+        let state = handle(State::new(&Config::new()), vec![screen(1, 0), screen(2, 100), window(10)]);
+        assert_eq!(vec!["1", "2"], screens(&state));
+        assert_eq!(Some(&Geometry::new(0, 0, 100, 100)), state.workspaces[0].get_current().unwrap().get_view());
+
+        // a workspace shown elsewhere gets the focus on its own screen
+        let state = handle(state, vec![key(vec![ModKey::Mod4], '2'), window(11)]);
+        assert_eq!(vec!["1", "2"], screens(&state));
+        assert_eq!((Some(1), "2"), (state.screens.get_current_index(), focus(&state).0));
+        assert_eq!(Some(&Geometry::new(100, 0, 100, 100)), state.workspaces[1].get_current().unwrap().get_view());
+
+        // a hidden one replaces the workspace of the focused screen
+        let state = handle(state, vec![key(vec![ModKey::Mod4], '3')]);
+        assert_eq!(vec!["1", "3"], screens(&state));
+        assert!(!state.workspaces[1].is_shown() && state.workspaces[2].is_shown());
+    }
+
+    #[test]
+    fn test_swap_visible_workspaces() {
+        let config = Config { swap_visible_workspaces: true, ..Config::new() };
+        let state = handle(State::new(&config), vec![screen(1, 0), screen(2, 100), window(10), key(vec![ModKey::Mod4], '2')]);
+        assert_eq!(vec!["2", "1"], screens(&state));
+        assert_eq!((Some(0), "2"), (state.screens.get_current_index(), focus(&state).0));
+        assert_eq!(Some(&Geometry::new(100, 0, 100, 100)), state.workspaces[0].get_current().unwrap().get_view());
+    }
+
+    #[test]
+    fn test_screen_changes() {
+        let state = handle(State::new(&Config::new()), vec![screen(1, 0), screen(2, 100), key(vec![ModKey::Mod4], '2'), window(10)]);
+        let state = handle(state, vec![Event::ScreenChanged(2, Geometry::new(100, 0, 200, 100))]);
+        assert_eq!(Some(&Geometry::new(100, 0, 200, 100)), state.workspaces[1].get_current().unwrap().get_view());
+
+        // the focused workspace follows the focus to the screen left
+        let state = handle(state, vec![Event::ScreenRemoved(2)]);
+        assert_eq!(vec!["2"], screens(&state));
+        assert_eq!(("2", Some(10)), focus(&state));
+        assert!(!state.workspaces[0].is_shown());
+        assert_eq!(Some(&Geometry::new(0, 0, 100, 100)), state.workspaces[1].get_current().unwrap().get_view());
+
+        let state = handle(state, vec![screen(3, 100)]);
+        assert_eq!(vec!["2", "1"], screens(&state));
+        let state = handle(state, vec![Event::ScreenRemoved(3)]);
+        assert_eq!(vec!["2"], screens(&state));
+        assert_eq!("2", focus(&state).0);
+        assert!(!state.workspaces[0].is_shown());
+        assert_eq!(1, handle(state, vec![Event::ScreenRemoved(3)]).screens.len());
+    }
+
+    #[test]
+    fn test_restored_screens() {
+        let state = handle(State::new(&Config::new()), vec![screen(1, 0), screen(2, 100), key(vec![ModKey::Mod4], '2')]);
+        let snapshot = state.snapshot();
+        assert_eq!((vec!["1".to_string(), "2".to_string()], Some("2".to_string())), (snapshot.screens.clone(), snapshot.current.clone()));
+
+        let snapshot = Snapshot { screens: vec!["3".to_string(), "2".to_string()], ..snapshot };
+        let state = State::new(&Config::new()).adopt(Vec::new(), Some(snapshot));
+        let state = handle(state, vec![screen(1, 0)]);
+        assert_eq!((vec!["3"], "3"), (screens(&state), focus(&state).0));
+        let state = handle(state, vec![screen(2, 100), screen(3, 200)]);
+        assert_eq!(vec!["3", "2", "1"], screens(&state));
+        assert_eq!((Some(1), "2"), (state.screens.get_current_index(), focus(&state).0));
+    }
+
+    #[test]
+    fn test_screen_navigation() {
+        let config = Config { warp_pointer: true, ..Config::new() };
+        let state = handle(State::new(&config), vec![screen(1, 0), screen(2, 100), screen(3, 200)]);
+        let state = handle(state, vec![key(vec![ModKey::Mod4], '-')]);
+        assert_eq!((Some(2), "3"), (state.screens.get_current_index(), focus(&state).0));
+        assert_eq!(Some(Geometry::new(200, 0, 100, 100)), state.warp_pointer);
+        let state = handle(state, vec![key(vec![ModKey::Mod4], '=')]);
+        assert_eq!((Some(0), "1"), (state.screens.get_current_index(), focus(&state).0));
+        let state = handle(state, vec![key(vec![ModKey::Mod4, ModKey::Control], '2')]);
+        assert_eq!((Some(1), "2"), (state.screens.get_current_index(), focus(&state).0));
+
+        // the window moves to the workspace of the other screen, the focus stays
+        let state = handle(state, vec![window(10), window(11), key(vec![ModKey::Mod4, ModKey::Control, ModKey::Shift], '1')]);
+        assert_eq!(("2", Some(10)), focus(&state));
+        assert_eq!(Some(&Geometry::new(0, 0, 100, 100)), state.workspaces[0].get_current().unwrap().get_view());
+
+        let state = handle(state, vec![key(vec![ModKey::Mod4, ModKey::Mod1], '3')]);
+        assert_eq!(vec!["1", "3", "2"], screens(&state));
+        assert_eq!((Some(1), "3"), (state.screens.get_current_index(), focus(&state).0));
+        assert_eq!(Some(&Geometry::new(200, 0, 100, 100)), state.workspaces[1].get_current().unwrap().get_view());
+    }
+
+    #[test]
+    fn test_arrange() {
+        let state = handle(State::new(&Config::new()), vec![screen(1, 0), window(10), window(11), key(vec![ModKey::Mod4], 'r')]);
+        let snapshot = Snapshot {
+            workspaces: vec![WorkspaceSnapshot { name: "1".to_string(), layout: 0, layouts: Vec::new(), tree: SplitTree::new(), focused: Some(10), windows: vec![10] }],
+            screens: Vec::new(),
+            current: Some("1".to_string()),
+        };
+        // the reflected layout survives the windows moving around
+        let state = state.arrange(snapshot);
+        assert_eq!(("1", Some(10)), focus(&state));
+        assert_eq!(Some(&Geometry::new(50, 0, 50, 100)), state.workspaces[0].get_current().unwrap().get_view());
+    }
+
+    fn command(command: &str) -> Event<u32, KeyCombo> {
+        Event::CommandReceived(command.to_string())
+    }
+
+    #[test]
+    fn test_session_commands() {
+        let state = handle(State::new(&Config::new()), vec![command("save_session work")]);
+        assert_eq!(Some(SessionRequest::Save("work".to_string())), state.session_request);
+        let state = handle(state.reset(), vec![command("restore_session ../work"), command("restore_session .work")]);
+        assert_eq!(None, state.session_request);
+        let state = handle(state, vec![command("restore_session work")]);
+        assert_eq!(Some(SessionRequest::Restore("work".to_string())), state.session_request);
+    }
+
+    #[test]
+    fn test_expected_windows() {
+        let properties = |class: &str, title: &str| {
+            Event::WindowPropertyChanged(10, WindowProperties { class: class.to_string(), instance: class.to_lowercase(), title: title.to_string(), role: String::new() })
+        };
+        let identity = WindowIdentity { class: "XTerm".to_string(), instance: "xterm".to_string(), ..WindowIdentity::default() };
+        let state = handle(State::new(&Config::new()), vec![screen(1, 0)]).expect_windows(vec![("3".to_string(), identity)]);
+
+        // only a window seen for the first time goes to its saved workspace
+        let state = handle(state, vec![window(10), properties("Emacs", "~"), properties("XTerm", "~")]);
+        assert_eq!(1, state.workspaces[0].len());
+        let state = handle(state, vec![Event::WindowRemoved(10), window(10), properties("XTerm", "~")]);
+        assert_eq!((0, 1), (state.workspaces[0].len(), state.workspaces[2].len()));
+        assert!(!state.workspaces[2].get_current().unwrap().is_visible());
+    }
+
+    fn names(state: &State<u32>) -> Vec<&str> {
+        state.workspaces.iter().map(|workspace| workspace.get_name()).collect()
+    }
+
+    #[test]
+    fn test_dynamic_workspaces() {
+        let config = Config { workspaces: vec!["1".to_string(), "2".to_string()], ..Config::new() };
+        let state = handle(State::new(&config), vec![screen(1, 0), command("add_workspace web")]);
+        assert_eq!(vec!["1", "2", "web"], names(&state));
+        assert_eq!((vec!["web"], "web"), (screens(&state), focus(&state).0));
+
+        let state = handle(state, vec![command("rename_workspace www"), command("rename_workspace"), command("rename_workspace 1")]);
+        assert_eq!(vec!["1", "2", "www"], names(&state));
+        let state = handle(state, vec![command("rename_workspace w\tw"), command("add_workspace w\nw")]);
+        assert_eq!(vec!["1", "2", "www"], names(&state));
+        assert_eq!((vec!["www"], "www"), (screens(&state), focus(&state).0));
+
+        let state = handle(state, vec![command("move_workspace_previous"), command("move_workspace_previous")]);
+        assert_eq!(vec!["www", "1", "2"], names(&state));
+        let state = handle(state, vec![command("move_workspace_next")]);
+        assert_eq!(vec!["1", "www", "2"], names(&state));
+        assert!(state.workspaces_changed);
+
+        // only empty workspaces can be deleted
+        let state = handle(state, vec![window(10), command("delete_workspace")]);
+        assert_eq!(vec!["1", "www", "2"], names(&state));
+        let state = handle(state, vec![Event::WindowRemoved(10), command("delete_workspace")]);
+        assert_eq!(vec!["1", "2"], names(&state));
+        assert_eq!((vec!["1"], "1"), (screens(&state), focus(&state).0));
+
+        // adding an existing workspace goes to it
+        let state = handle(state, vec![command("add_workspace 2"), command("unknown")]);
+        assert_eq!((vec!["1", "2"], "2"), (names(&state), focus(&state).0));
+    }
+
+    #[test]
+    fn test_rename_previous_workspace() {
+        let config = Config { workspaces: vec!["1".to_string(), "2".to_string()], ..Config::new() };
+        let state = handle(State::new(&config), vec![screen(1, 0), screen(2, 100)]);
+        // focusing a screen leaves the workspace it shows as the previous one
+        let state = state.goto_workspace(1).focus_screen(0);
+        assert_eq!(Some("1"), state.previous_workspace.as_deref());
+        let state = state.rename_workspace("one".to_string());
+        assert_eq!(Some("one"), state.previous_workspace.as_deref());
+        assert_eq!((vec!["one", "2"], "one"), (names(&state), focus(&state).0));
+    }
+}
@@ -27,18 +27,22 @@ impl<W> Deref for Window<W> {
     }
 }
 
-#[derive(Clone)]
+/// What the client says about itself, empty when it does not say.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct WindowProperties {
+    pub class: String,
+    pub instance: String,
+    pub title: String,
+    pub role: String,
+}
+
+#[derive(Clone, PartialEq, Eq)]
 pub struct Window<W> {
     id: W,
     window_type: WindowType,
     view: Option<Geometry>,
     visible: bool,
-}
-
-impl<W: Eq> PartialEq for Window<W> {
-    fn eq(&self, other: &Self) -> bool {
-        self == other
-    }
+    properties: WindowProperties,
 }
 
 impl fmt::Debug for Size {
@@ -61,10 +65,9 @@ impl fmt::Debug for Geometry {
 
 impl<W: Debug> fmt::Debug for Window<W> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.view.is_none() {
-            write!(f, "({:?} - {:?} - {})", &self.id, &self.window_type, "Unknown")
-        } else {
-            write!(f, "({:?} - {:?} - {:?})", &self.id, &self.window_type, self.view.as_ref().unwrap())
+        match &self.view {
+            Some(view) => write!(f, "({:?} - {:?} - {:?})", &self.id, &self.window_type, view),
+            None => write!(f, "({:?} - {:?} - Unknown)", &self.id, &self.window_type),
         }
     }
 }
@@ -98,7 +101,7 @@ impl Geometry {
 
 impl<W> Window<W> {
     pub fn new(id: W, window_type: WindowType) -> Self {
-        Window { id, window_type, view: None, visible: false }
+        Window { id, window_type, view: None, visible: false, properties: WindowProperties::default() }
     }
 
     pub fn is_visible(&self) -> bool {
@@ -115,7 +118,16 @@ impl<W> Window<W> {
         self
     }
 
-    pub fn get_view(&self) -> &Geometry {
-        self.view.as_ref().unwrap()
+    pub fn get_view(&self) -> Option<&Geometry> {
+        self.view.as_ref()
+    }
+
+    pub fn set_properties(mut self, properties: WindowProperties) -> Self {
+        self.properties = properties;
+        self
+    }
+
+    pub fn get_properties(&self) -> &WindowProperties {
+        &self.properties
     }
 }
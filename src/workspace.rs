@@ -1,7 +1,9 @@
 use crate::layouts::Layout;
-use crate::window::{Window, Geometry};
+use crate::layouts::manual::{self, SplitTree, Direction};
+use crate::layouts::modifiers::{self, Modifier};
+use crate::window::{Window, Geometry, WindowProperties};
 use std::ops::Deref;
-use crate::stack::Stack;
+use crate::stack::{Stack, FocusPolicy};
 use std::fmt::{self, Debug};
 
 impl<W: Debug + Eq + Clone> PartialEq for Workspace<W> {
@@ -20,9 +22,14 @@ impl<W: Debug + Eq + Clone> Debug for Workspace<W> {
 pub struct Workspace<W> {
     name: String,
     is_changed: bool,
+    shown: bool,
     view: Option<Geometry>,
     windows: Stack<Window<W>>,
     layouts: Stack<Layout>,
+    tree: SplitTree<W>,
+    modifiers: Vec<Modifier>,
+    focus_policy: FocusPolicy,
+    focus_history: Vec<W>,
 }
 
 impl<W> Deref for Workspace<W> {
@@ -34,20 +41,19 @@ impl<W> Deref for Workspace<W> {
 }
 
 impl<W: Debug + Eq + Clone> Workspace<W> {
-    pub fn new(name: String, windows: Stack<Window<W>>, layouts: Stack<Layout>) -> Self {
-        let workspace = Self { name, windows, layouts, is_changed: false, view: None };
-        workspace.perform_layout()
+    pub fn new(name: String, windows: Stack<Window<W>>, layouts: Stack<Layout>, modifiers: Vec<Modifier>, focus_policy: FocusPolicy) -> Self {
+        let tree = windows.iter().fold(SplitTree::new(), |tree, window| tree.insert(None, window.deref().clone()));
+        let workspace = Self { name, windows, layouts, tree, modifiers, focus_policy, focus_history: Vec::new(), is_changed: false, shown: false, view: None };
+        workspace.record_focus().perform_layout()
     }
 
     pub fn visible(mut self, visible: bool) -> Self {
-        if visible {
-            self.perform_layout()
-        } else {
-            self.windows = self.windows.into_iter()
-                .map(|(is_current, window)| (is_current, window.visible(false)))
-                .collect();
-            self.need_update()
-        }
+        self.shown = visible;
+        self.perform_layout()
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.shown
     }
 
     pub fn set_view(mut self, view: Geometry) -> Self {
@@ -55,6 +61,53 @@ impl<W: Debug + Eq + Clone> Workspace<W> {
         self
     }
 
+    pub fn get_layout_index(&self) -> Option<usize> {
+        self.layouts.get_current_index()
+    }
+
+    pub fn set_layout(mut self, index: usize) -> Self {
+        self.layouts = self.layouts.set_current(index);
+        self.perform_layout()
+    }
+
+    pub fn get_layouts(&self) -> Vec<Layout> {
+        self.layouts.iter().cloned().collect()
+    }
+
+    /// Takes the parameters of the saved layouts which are still of the same
+    /// kind as the configured ones.
+    pub fn set_layouts(mut self, layouts: &[Layout]) -> Self {
+        self.layouts = self.layouts.into_iter()
+            .zip(layouts.iter().map(Some).chain(std::iter::repeat(None)))
+            .map(|((is_current, layout), saved)| match saved {
+                Some(saved) if saved.is_same_kind(&layout) => (is_current, saved.clone()),
+                _ => (is_current, layout),
+            })
+            .collect();
+        self.perform_layout()
+    }
+
+    pub fn change_ratio(mut self, delta: f32) -> Self {
+        self.layouts = self.layouts.update_current(|layout| layout.change_ratio(delta));
+        self.perform_layout()
+    }
+
+    pub fn change_master_count(mut self, delta: isize) -> Self {
+        self.layouts = self.layouts.update_current(|layout| layout.change_master_count(delta));
+        self.perform_layout()
+    }
+
+    pub fn get_tab_bar(&self) -> Option<Geometry> {
+        let view = self.view.as_ref()?;
+        self.layouts.get_current()?.tab_bar(view)
+    }
+
+    pub fn rename(mut self, name: String) -> Self {
+        log::debug!("Rename workspace {} to {}", self.get_name(), &name);
+        self.name = name;
+        self.perform_layout()
+    }
+
     pub fn get_name(&self) -> &str {
         &self.name
     }
@@ -76,39 +129,188 @@ impl<W: Debug + Eq + Clone> Workspace<W> {
     pub fn next_window(mut self) -> Self {
         log::debug!("Focus next window");
         self.windows = self.windows.next();
-        self.perform_layout()
+        self.record_focus().perform_layout()
     }
 
     pub fn previous_window(mut self) -> Self {
         log::debug!("Focus previous window");
         self.windows = self.windows.previous();
+        self.record_focus().perform_layout()
+    }
+
+    pub fn focus_window(mut self, window: W) -> Self {
+        match self.windows.iter().position(|w| w.deref() == &window) {
+            Some(index) => {
+                log::debug!("Focus window id {:?}", &window);
+                self.windows = self.windows.set_current(index);
+                self.record_focus().perform_layout()
+            },
+            None => self,
+        }
+    }
+
+    pub fn swap_next_window(mut self) -> Self {
+        log::debug!("Swap window with next window");
+        self.windows = self.windows.swap_next();
+        self.perform_layout()
+    }
+
+    pub fn swap_previous_window(mut self) -> Self {
+        log::debug!("Swap window with previous window");
+        self.windows = self.windows.swap_previous();
+        self.perform_layout()
+    }
+
+    pub fn swap_master_window(mut self) -> Self {
+        log::debug!("Swap window with master window");
+        self.windows = self.windows.swap_with_head();
+        self.perform_layout()
+    }
+
+    pub fn rotate_windows(mut self) -> Self {
+        log::debug!("Rotate windows");
+        self.windows = self.windows.rotate();
         self.perform_layout()
     }
 
+    pub fn take_current_window(self) -> (Self, Option<Window<W>>) {
+        match self.windows.get_current().cloned() {
+            Some(window) => (self.remove_window(window.deref().clone()), Some(window)),
+            None => (self, None),
+        }
+    }
+
     pub fn add_window(mut self, window: Window<W>) -> Self {
         log::debug!("Adding window id {:?} to workspace {}", &window.deref(), self.get_name());
+        let focused = self.windows.get_current().map(|w| w.deref());
+        self.tree = self.tree.insert(focused, window.deref().clone());
         self.windows = self.windows.add(window);
+        self.record_focus().perform_layout()
+    }
+
+    /// Replaces the windows, the ones staying keep their place in the split
+    /// tree and in the focus history.
+    pub fn set_windows(mut self, windows: Stack<Window<W>>) -> Self {
+        self.focus_history.retain(|window| windows.iter().any(|w| w.deref() == window));
+        self.windows = windows;
+        let tree = self.tree.clone();
+        self.record_focus().set_tree(tree)
+    }
+
+    pub fn get_tree(&self) -> &SplitTree<W> {
+        &self.tree
+    }
+
+    /// Uses the given split tree for the windows it holds, the others are
+    /// added to it.
+    pub fn set_tree(mut self, tree: SplitTree<W>) -> Self {
+        let windows = &self.windows;
+        let tree = tree.filter_map(|window| windows.iter().any(|w| w.deref() == window).then(|| window.clone()));
+        let missing = self.windows.iter()
+            .map(|window| window.deref().clone())
+            .filter(|window| !tree.contains(window))
+            .collect::<Vec<_>>();
+        self.tree = missing.into_iter().fold(tree, |tree, window| tree.insert(None, window));
         self.perform_layout()
     }
 
+    pub fn set_properties(mut self, window: &W, properties: &WindowProperties) -> Self {
+        self.windows = self.windows.into_iter()
+            .map(|(is_current, w)| {
+                if w.deref() == window { (is_current, w.set_properties(properties.clone())) } else { (is_current, w) }
+            })
+            .collect();
+        self
+    }
+
     pub fn remove_window(mut self, window: W) -> Self {
         log::debug!("Removing window id {:?} from workspace {}", &window, self.get_name());
         let old_len = self.windows.len();
-        self.windows = self.windows.remove_by(|w| w.deref() == &window);
-        if old_len != self.windows.len() {
-            self.perform_layout()
+        self.windows = self.windows.remove_by(|w| w.deref() == &window, self.focus_policy);
+        self.tree = self.tree.remove(&window);
+        self.focus_history.retain(|w| w != &window);
+        if old_len == self.windows.len() {
+            return self;
+        }
+        if self.focus_policy == FocusPolicy::MostRecent {
+            let recent = self.focus_history.last()
+                .and_then(|recent| self.windows.iter().position(|w| w.deref() == recent));
+            if let Some(index) = recent {
+                self.windows = self.windows.set_current(index);
+            }
+        }
+        self.record_focus().perform_layout()
+    }
+
+    fn record_focus(mut self) -> Self {
+        if let Some(window) = self.windows.get_current() {
+            let window = window.deref().clone();
+            self.focus_history.retain(|w| w != &window);
+            self.focus_history.push(window);
+        }
+        self
+    }
+
+    pub fn preselect_split(mut self, direction: Direction) -> Self {
+        log::debug!("Preselect {:?} split", &direction);
+        self.tree = self.tree.preselect(direction);
+        self
+    }
+
+    pub fn toggle_split_direction(mut self) -> Self {
+        if let Some(window) = self.windows.get_current() {
+            self.tree = self.tree.toggle_direction(window.deref());
+        }
+        self.perform_layout()
+    }
+
+    pub fn resize_split(mut self, delta: f32) -> Self {
+        if let Some(window) = self.windows.get_current() {
+            self.tree = self.tree.resize(window.deref(), delta);
+        }
+        self.perform_layout()
+    }
+
+    pub fn move_split_window(mut self, forward: bool) -> Self {
+        if let Some(window) = self.windows.get_current() {
+            self.tree = self.tree.move_window(window.deref(), forward);
+        }
+        self.perform_layout()
+    }
+
+    pub fn toggle_modifier(mut self, modifier: Modifier) -> Self {
+        let old_len = self.modifiers.len();
+        self.modifiers.retain(|m| !m.is_same_kind(&modifier));
+        if old_len == self.modifiers.len() {
+            log::debug!("Enable modifier {:?} on workspace {}", &modifier, self.get_name());
+            self.modifiers.push(modifier);
         } else {
-            self
+            log::debug!("Disable modifier {:?} on workspace {}", &modifier, self.get_name());
         }
+        self.perform_layout()
     }
 
     fn perform_layout(mut self) -> Self {
-        if self.windows.is_empty() || self.view.is_none() {
+        if !self.shown {
+            self.windows = self.windows.into_iter()
+                .map(|(is_current, window)| (is_current, window.visible(false)))
+                .collect();
+            return self.need_update();
+        }
+        if self.view.is_none() {
             return self;
         }
+        if self.windows.is_empty() {
+            return self.need_update();
+        }
         let layout = self.layouts.get_current().unwrap();
         log::debug!("Updating layout for workspace {} using {:?}", &self.name, &layout);
-        let handled_windows = layout.handle_layout(&self.view.as_ref().unwrap(), self.windows);
+        let tree = &self.tree;
+        let arrange = |view: &Geometry, windows| match layout {
+            Layout::Manual => manual::handle_layout(view, windows, tree),
+            layout => layout.handle_layout(view, windows),
+        };
+        let handled_windows = modifiers::handle_layout(arrange, &self.modifiers, &self.view.as_ref().unwrap(), self.windows);
         self.windows = handled_windows;
         self.need_update()
     }
@@ -0,0 +1,279 @@
+//! Runs the window manager against a headless X server and checks the result
+//! from a separate client connection. Skipped when `Xvfb` is not installed.
+use rwm::config::Config;
+use rwm::manager::Manager;
+use rwm::displays::xcb_server::XcbDisplayServer;
+use xcb_util::ewmh;
+use xcb_util::keysyms::KeySymbols;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const SUPER: u32 = 0xffeb;
+const SHIFT: u32 = 0xffe1;
+
+/// Display numbers are picked from the free lock files, so start one Xvfb at a time.
+static DISPLAY: Mutex<()> = Mutex::new(());
+
+fn eventually<F: FnMut() -> bool>(mut condition: F) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if condition() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    condition()
+}
+
+fn start_manager(config: Config) -> JoinHandle<rwm::Result<()>> {
+    thread::spawn(move || {
+        let mut runtime = tokio::runtime::Builder::new().basic_scheduler().enable_all().build().unwrap();
+        runtime.block_on(async {
+            Manager::<XcbDisplayServer>::new(config)?.stream().await;
+            Ok(())
+        })
+    })
+}
+
+struct Xvfb {
+    process: Child,
+    name: String,
+}
+
+impl Xvfb {
+    fn start() -> Option<Self> {
+        let number = (90..200).find(|n| !Path::new(&format!("/tmp/.X{}-lock", n)).exists())?;
+        let name = format!(":{}", number);
+        let process = Command::new("Xvfb")
+            .args([&name, "-screen", "0", "1024x768x24", "-nolisten", "tcp"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        match process {
+            Ok(process) => {
+                let xvfb = Xvfb { process, name };
+                assert!(eventually(|| xcb::Connection::connect(Some(&xvfb.name)).is_ok()), "Xvfb did not start");
+                Some(xvfb)
+            },
+            Err(_) => {
+                eprintln!("Xvfb is not installed, skipping");
+                None
+            },
+        }
+    }
+}
+
+impl Drop for Xvfb {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+/// A window manager running on its own Xvfb, plus a client connection to drive it.
+struct Harness {
+    connection: ewmh::Connection,
+    root: xcb::Window,
+    root_visual: xcb::Visualid,
+    manager: Option<JoinHandle<rwm::Result<()>>>,
+    display: String,
+    _xvfb: Xvfb,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Harness {
+    fn start() -> Option<Self> {
+        let lock = DISPLAY.lock().unwrap_or_else(|e| e.into_inner());
+        let xvfb = Xvfb::start()?;
+        let display = xvfb.name.clone();
+        let manager = start_manager(Config { display: Some(display.clone()), ..Config::new() });
+
+        let (connection, screen_num) = xcb::Connection::connect(Some(&xvfb.name)).unwrap();
+        let connection = ewmh::Connection::connect(connection).map_err(|e| e.0).unwrap();
+        let (root, root_visual) = {
+            let screen = connection.get_setup().roots().nth(screen_num as usize).unwrap();
+            (screen.root(), screen.root_visual())
+        };
+        let harness = Harness { connection, root, root_visual, manager: Some(manager), display, _xvfb: xvfb, _lock: lock };
+        assert!(eventually(|| harness.is_managed()), "window manager did not start");
+        Some(harness)
+    }
+
+    fn config(&self, replace: bool) -> Config {
+        Config { display: Some(self.display.clone()), replace, ..Config::new() }
+    }
+
+    fn is_managed(&self) -> bool {
+        let attributes = xcb::get_window_attributes(&self.connection, self.root).get_reply().unwrap();
+        attributes.all_event_masks() & xcb::EVENT_MASK_SUBSTRUCTURE_REDIRECT != 0
+    }
+
+    fn create_window(&self) -> xcb::Window {
+        let window = self.connection.generate_id();
+        xcb::create_window(
+            &self.connection,
+            xcb::COPY_FROM_PARENT as u8,
+            window,
+            self.root,
+            0, 0, 100, 100, 0,
+            xcb::WINDOW_CLASS_INPUT_OUTPUT as u16,
+            self.root_visual,
+            &[],
+        );
+        xcb::map_window(&self.connection, window);
+        self.connection.flush();
+        window
+    }
+
+    fn destroy_window(&self, window: xcb::Window) {
+        xcb::destroy_window(&self.connection, window);
+        self.connection.flush();
+    }
+
+    fn press(&self, mod_keys: &[u32], key: u32) {
+        let key_symbols = KeySymbols::new(&self.connection);
+        let keycode = |keysym| key_symbols.get_keycode(keysym).next().unwrap();
+        let fake_input = |event, keysym| {
+            xcb::test::fake_input(&self.connection, event, keycode(keysym), xcb::CURRENT_TIME, xcb::NONE, 0, 0, 0);
+        };
+        mod_keys.iter().for_each(|&keysym| fake_input(xcb::KEY_PRESS, keysym));
+        fake_input(xcb::KEY_PRESS, key);
+        fake_input(xcb::KEY_RELEASE, key);
+        mod_keys.iter().rev().for_each(|&keysym| fake_input(xcb::KEY_RELEASE, keysym));
+        self.connection.flush();
+    }
+
+    fn is_mapped(&self, window: xcb::Window) -> bool {
+        let attributes = xcb::get_window_attributes(&self.connection, window).get_reply().unwrap();
+        attributes.map_state() == xcb::MAP_STATE_VIEWABLE as u8
+    }
+
+    fn geometry(&self, window: xcb::Window) -> (i16, i16, u16, u16) {
+        let geometry = xcb::get_geometry(&self.connection, window).get_reply().unwrap();
+        (geometry.x(), geometry.y(), geometry.width(), geometry.height())
+    }
+
+    /// The given window is stacked above all the other ones.
+    fn is_on_top(&self, window: xcb::Window, others: &[xcb::Window]) -> bool {
+        let tree = xcb::query_tree(&self.connection, self.root).get_reply().unwrap();
+        let position = |window| tree.children().iter().position(|&child| child == window);
+        others.iter().all(|&other| position(window) > position(other))
+    }
+
+    /// The window has the keyboard focus and is the EWMH active window.
+    fn is_focused(&self, window: xcb::Window) -> bool {
+        let focus = xcb::get_input_focus(&self.connection).get_reply().unwrap().focus();
+        let active = ewmh::get_active_window(&self.connection, 0).get_reply().ok();
+        focus == window && active == Some(window)
+    }
+
+    /// No window has the keyboard focus.
+    fn is_unfocused(&self) -> bool {
+        let focus = xcb::get_input_focus(&self.connection).get_reply().unwrap().focus();
+        let active = ewmh::get_active_window(&self.connection, 0).get_reply().ok();
+        [xcb::NONE, xcb::INPUT_FOCUS_POINTER_ROOT, self.root].contains(&focus) && active.unwrap_or(xcb::NONE) == xcb::NONE
+    }
+
+    fn current_desktop(&self) -> u32 {
+        ewmh::get_current_desktop(&self.connection, 0).get_reply().unwrap()
+    }
+
+    fn quit(mut self) {
+        self.press(&[SUPER, SHIFT], 'q' as u32);
+        let manager = self.manager.take().unwrap();
+        assert!(eventually(|| manager.is_finished()), "window manager did not quit");
+        manager.join().unwrap().unwrap();
+    }
+}
+
+#[test]
+fn test_windows_are_tiled() {
+    let harness = match Harness::start() {
+        Some(harness) => harness,
+        None => return,
+    };
+    let first = harness.create_window();
+    assert!(eventually(|| harness.is_mapped(first)));
+    assert!(eventually(|| harness.geometry(first) == (0, 0, 1024, 768)));
+    assert!(eventually(|| harness.is_focused(first)));
+
+    let second = harness.create_window();
+    assert!(eventually(|| harness.is_mapped(second)));
+    assert!(eventually(|| harness.geometry(second) == (0, 0, 512, 768)));
+    assert!(eventually(|| harness.geometry(first) == (512, 0, 512, 768)));
+    assert!(eventually(|| harness.is_on_top(second, &[first])));
+    assert!(eventually(|| harness.is_focused(second)));
+
+    harness.destroy_window(second);
+    assert!(eventually(|| harness.geometry(first) == (0, 0, 1024, 768)));
+    assert!(eventually(|| harness.is_focused(first)));
+
+    harness.destroy_window(first);
+    assert!(eventually(|| harness.is_unfocused()));
+    harness.quit();
+}
+
+#[test]
+fn test_key_presses() {
+    let harness = match Harness::start() {
+        Some(harness) => harness,
+        None => return,
+    };
+    let first = harness.create_window();
+    let second = harness.create_window();
+    assert!(eventually(|| harness.is_on_top(second, &[first])));
+    assert!(eventually(|| harness.is_focused(second)));
+
+    harness.press(&[SUPER], 'j' as u32);
+    assert!(eventually(|| harness.is_on_top(first, &[second])));
+    assert!(eventually(|| harness.is_focused(first)));
+
+    harness.press(&[SUPER], '2' as u32);
+    assert!(eventually(|| harness.current_desktop() == 1));
+    assert!(eventually(|| !harness.is_mapped(first) && !harness.is_mapped(second)));
+    assert!(eventually(|| harness.is_unfocused()));
+
+    harness.press(&[SUPER], '1' as u32);
+    assert!(eventually(|| harness.current_desktop() == 0));
+    assert!(eventually(|| harness.is_mapped(first) && harness.is_mapped(second)));
+    assert!(eventually(|| harness.is_focused(first)));
+    harness.quit();
+}
+
+#[test]
+fn test_ewmh_desktops() {
+    let harness = match Harness::start() {
+        Some(harness) => harness,
+        None => return,
+    };
+    assert!(eventually(|| ewmh::get_number_of_desktops(&harness.connection, 0).get_reply().ok() == Some(9)));
+    let names = ewmh::get_desktop_names(&harness.connection, 0).get_reply().unwrap();
+    assert_eq!((1..=9).map(|i| i.to_string()).collect::<Vec<_>>(), names.strings());
+    assert_eq!(0, harness.current_desktop());
+    harness.quit();
+}
+
+#[test]
+fn test_replace() {
+    let mut harness = match Harness::start() {
+        Some(harness) => harness,
+        None => return,
+    };
+    let window = harness.create_window();
+    assert!(eventually(|| harness.is_mapped(window)));
+
+    match start_manager(harness.config(false)).join().unwrap() {
+        Err(rwm::Error::OtherWindowManager) => {},
+        _ => panic!("started next to a running window manager"),
+    }
+
+    let replacement = start_manager(harness.config(true));
+    let replaced = harness.manager.replace(replacement).unwrap();
+    assert!(eventually(|| replaced.is_finished()), "window manager was not replaced");
+    replaced.join().unwrap().unwrap();
+    assert!(eventually(|| harness.is_managed()));
+    harness.quit();
+}